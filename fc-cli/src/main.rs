@@ -1,3 +1,7 @@
+mod output;
+
+use std::collections::HashSet;
+use std::ffi::{OsStr, OsString};
 use std::num::NonZeroU64;
 use std::os::unix::fs::chown;
 use std::path::{Path, PathBuf};
@@ -6,8 +10,13 @@ use std::time::Duration;
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use firecracker::runtime::bundled::{BundledMode, BundledRuntimeOptions};
 use firecracker::sdk::{
-    FirecrackerProcess, FirecrackerProcessBuilder, JailerProcessBuilder, types,
+    FirecrackerProcess, FirecrackerProcessBuilder, JailerProcessBuilder, LogLevel, Vm, connection,
+    types,
 };
+use serde::Serialize;
+use tokio::signal::unix::{SignalKind, signal};
+
+use crate::output::{OutputFormat, emit};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -16,6 +25,13 @@ use firecracker::sdk::{
     about = "CLI utilities for Firecracker SDK runtime operations"
 )]
 struct Cli {
+    /// Output format for command results.
+    ///
+    /// `json` prints one JSON object per line. An attached `start` prints two:
+    /// one when the microVM is running and one with `exit_status` when it exits.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -26,6 +42,19 @@ enum Commands {
     Resolve(ResolveArgs),
     /// Spawn Firecracker and start a microVM.
     Start(Box<StartArgs>),
+    /// Pause a running microVM, snapshot it, and resume it.
+    Snapshot(SnapshotArgs),
+    /// Load a snapshot into an already-running, unconfigured Firecracker process.
+    Restore(RestoreArgs),
+    /// Pause a running microVM.
+    Pause(SocketArgs),
+    /// Resume a paused microVM.
+    Resume(SocketArgs),
+    /// Send Ctrl+Alt+Del and wait for the microVM to stop running.
+    #[command(alias = "stop")]
+    Shutdown(ShutdownArgs),
+    /// Print instance info and attached devices of a running microVM.
+    Inspect(SocketArgs),
     /// Print current platform and whether release-based bundled mode supports it.
     Platform,
 }
@@ -63,6 +92,61 @@ struct ResolveArgs {
     runtime: RuntimeArgs,
 }
 
+#[derive(Debug, Clone, Args)]
+struct SocketArgs {
+    /// Firecracker API socket path of the running microVM.
+    #[arg(long, alias = "api-sock", default_value = "/tmp/firecracker.socket")]
+    socket_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+struct ShutdownArgs {
+    #[command(flatten)]
+    socket: SocketArgs,
+
+    /// How long to wait for the microVM to leave the running state (seconds).
+    #[arg(long, default_value_t = 10)]
+    timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Args)]
+struct SnapshotArgs {
+    /// Firecracker API socket path of the running microVM.
+    #[arg(long, alias = "api-sock", default_value = "/tmp/firecracker.socket")]
+    socket_path: PathBuf,
+
+    /// Output path for the microVM state file.
+    #[arg(long)]
+    snapshot_path: PathBuf,
+
+    /// Output path for the guest memory file.
+    #[arg(long)]
+    mem_file_path: PathBuf,
+
+    /// Create a diff snapshot (requires dirty page tracking before Firecracker 1.13).
+    #[arg(long, default_value_t = false)]
+    diff: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+struct RestoreArgs {
+    /// Firecracker API socket path of a fresh (unconfigured) process.
+    #[arg(long, alias = "api-sock", default_value = "/tmp/firecracker.socket")]
+    socket_path: PathBuf,
+
+    /// Path to the microVM state file.
+    #[arg(long)]
+    snapshot_path: PathBuf,
+
+    /// Path to the guest memory file.
+    #[arg(long)]
+    mem_file_path: PathBuf,
+
+    /// Resume the microVM after the snapshot is loaded.
+    #[arg(long, default_value_t = false)]
+    resume: bool,
+}
+
 #[derive(Debug, Clone, Args)]
 struct StartArgs {
     #[command(flatten)]
@@ -139,8 +223,10 @@ struct StartArgs {
     initrd: Option<PathBuf>,
 
     /// Root filesystem path.
+    ///
+    /// Optional when a root drive is passed via `--drive ...,root=true`.
     #[arg(long)]
-    rootfs: PathBuf,
+    rootfs: Option<PathBuf>,
 
     /// Root block device id.
     #[arg(long, default_value = "rootfs")]
@@ -150,6 +236,14 @@ struct StartArgs {
     #[arg(long, default_value_t = false)]
     rootfs_read_only: bool,
 
+    /// Additional block device (repeatable), e.g. `id=data,path=/data.ext4,readonly=true,root=false`.
+    #[arg(long = "drive", value_parser = parse_drive_spec)]
+    drives: Vec<DriveSpec>,
+
+    /// Network interface (repeatable), e.g. `id=eth0,tap=tap0,mac=AA:FC:00:00:00:01`.
+    #[arg(long = "net", value_parser = parse_net_spec)]
+    nets: Vec<NetSpec>,
+
     /// Kernel boot arguments.
     #[arg(long)]
     boot_args: Option<String>,
@@ -174,6 +268,26 @@ struct StartArgs {
     #[arg(long, default_value_t = false)]
     no_seccomp: bool,
 
+    /// Custom seccomp filter file for the Firecracker process.
+    #[arg(long, conflicts_with = "no_seccomp")]
+    seccomp_filter: Option<PathBuf>,
+
+    /// Enable the boot timer device for guest boot-time measurement.
+    #[arg(long, default_value_t = false)]
+    boot_timer: bool,
+
+    /// Maximum HTTP API request payload size (bytes).
+    #[arg(long)]
+    http_api_max_payload_size: Option<usize>,
+
+    /// Maximum MMDS data store size (bytes).
+    #[arg(long)]
+    mmds_size_limit: Option<usize>,
+
+    /// Enable PCI support in the guest.
+    #[arg(long, default_value_t = false)]
+    enable_pci: bool,
+
     /// Firecracker log output path.
     #[arg(long)]
     log_path: Option<PathBuf>,
@@ -182,9 +296,9 @@ struct StartArgs {
     #[arg(long)]
     metrics_path: Option<PathBuf>,
 
-    /// Firecracker log level.
+    /// Firecracker log level (off, error, warn, info, debug, trace).
     #[arg(long)]
-    log_level: Option<String>,
+    log_level: Option<LogLevel>,
 
     /// Socket readiness timeout (seconds).
     #[arg(long, default_value_t = 5)]
@@ -199,6 +313,23 @@ struct StartArgs {
     detach: bool,
 }
 
+/// Block device parsed from `--drive`.
+#[derive(Debug, Clone)]
+struct DriveSpec {
+    id: String,
+    path: PathBuf,
+    read_only: bool,
+    root: bool,
+}
+
+/// Network interface parsed from `--net`.
+#[derive(Debug, Clone)]
+struct NetSpec {
+    id: String,
+    tap: String,
+    mac: Option<String>,
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum ResolveTarget {
     Firecracker,
@@ -223,38 +354,143 @@ enum StartBackend {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let output = cli.output;
     match cli.command {
-        Commands::Resolve(args) => resolve(args)?,
-        Commands::Start(args) => start(*args).await?,
-        Commands::Platform => platform(),
+        Commands::Resolve(args) => resolve(args, output)?,
+        Commands::Start(args) => start(*args, output).await?,
+        Commands::Snapshot(args) => snapshot(args, output).await?,
+        Commands::Restore(args) => restore(args, output).await?,
+        Commands::Pause(args) => pause(args, output).await?,
+        Commands::Resume(args) => resume(args, output).await?,
+        Commands::Shutdown(args) => shutdown(args, output).await?,
+        Commands::Inspect(args) => inspect(args, output).await?,
+        Commands::Platform => platform(output)?,
     }
     Ok(())
 }
 
-fn resolve(args: ResolveArgs) -> Result<(), Box<dyn std::error::Error>> {
+#[derive(Debug, Serialize)]
+struct ResolveOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    firecracker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jailer: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StartOutput {
+    vm_started: bool,
+    backend: &'static str,
+    detached: bool,
+    socket: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    waiting: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExitOutput {
+    exit_status: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotOutput {
+    snapshot_created: bool,
+    snapshot_type: &'static str,
+    snapshot_path: String,
+    mem_file_path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreOutput {
+    restored: bool,
+    resumed: bool,
+    socket: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PauseOutput {
+    paused: bool,
+    socket: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ResumeOutput {
+    resumed: bool,
+    socket: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ShutdownOutput {
+    shutdown: bool,
+    socket: String,
+}
+
+#[derive(Debug, Serialize)]
+struct InspectOutput {
+    id: String,
+    state: types::InstanceInfoState,
+    vmm_version: String,
+    socket: String,
+    drives: Vec<DriveOutput>,
+    network_interfaces: Vec<NetOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct DriveOutput {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    socket: Option<String>,
+    read_only: bool,
+    root: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct NetOutput {
+    id: String,
+    tap: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mac: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PlatformOutput {
+    os: &'static str,
+    arch: &'static str,
+    bundled_release_supported: bool,
+}
+
+fn resolve(args: ResolveArgs, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     let opts = build_runtime_options(&args.runtime);
 
-    match args.target {
-        ResolveTarget::Firecracker => {
-            let path = opts.resolve_firecracker_bin()?;
-            println!("firecracker={}", path.display());
-        }
-        ResolveTarget::Jailer => {
-            let path = opts.resolve_jailer_bin()?;
-            println!("jailer={}", path.display());
-        }
-        ResolveTarget::All => {
-            let firecracker = opts.resolve_firecracker_bin()?;
-            let jailer = opts.resolve_jailer_bin()?;
-            println!("firecracker={}", firecracker.display());
-            println!("jailer={}", jailer.display());
-        }
-    }
+    let resolve_firecracker =
+        matches!(args.target, ResolveTarget::Firecracker | ResolveTarget::All);
+    let resolve_jailer = matches!(args.target, ResolveTarget::Jailer | ResolveTarget::All);
 
-    Ok(())
+    let firecracker = if resolve_firecracker {
+        Some(path_to_string(&opts.resolve_firecracker_bin()?))
+    } else {
+        None
+    };
+    let jailer = if resolve_jailer {
+        Some(path_to_string(&opts.resolve_jailer_bin()?))
+    } else {
+        None
+    };
+
+    emit(
+        output,
+        &ResolveOutput {
+            firecracker,
+            jailer,
+        },
+    )
 }
 
-async fn start(args: StartArgs) -> Result<(), Box<dyn std::error::Error>> {
+async fn start(args: StartArgs, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     let runtime_options = build_runtime_options(&args.runtime);
 
     let vcpu_count = NonZeroU64::new(args.vcpu_count)
@@ -272,6 +508,7 @@ async fn start(args: StartArgs) -> Result<(), Box<dyn std::error::Error>> {
     if matches!(args.backend, StartBackend::Jailer) && args.daemonize && !args.detach {
         return Err(invalid_input("`--backend jailer --daemonize` requires `--detach`").into());
     }
+    let drives = collect_drives(&args)?;
 
     let mut process = spawn_process(&args, &runtime_options).await?;
 
@@ -279,57 +516,196 @@ async fn start(args: StartArgs) -> Result<(), Box<dyn std::error::Error>> {
     // chroot-relative paths for the Firecracker API.
     let vm_paths = match args.backend {
         StartBackend::Jailer => {
-            let chroot_root = chroot_root_from_socket(process.socket_path())?;
-            stage_jailer_resources(&chroot_root, &args)?
+            let layout = process
+                .jailer_layout()
+                .ok_or_else(|| invalid_input("jailer process has no chroot layout"))?;
+            stage_jailer_resources(&layout.chroot_root, &args, &drives)?
         }
         StartBackend::Firecracker => VmPaths {
             kernel: args.kernel.clone(),
-            rootfs: args.rootfs.clone(),
             initrd: args.initrd.clone(),
+            drives,
         },
     };
 
     configure_vm(&process, &args, vcpu_count, &vm_paths).await?;
 
-    println!("vm_started=true");
-    println!("backend={}", backend_as_str(args.backend));
+    let backend = backend_as_str(args.backend);
 
     if args.detach {
         let detached = process.detach();
-        println!("detached=true");
-        println!("socket={}", detached.socket_path().display());
-        if let Some(pid) = detached.pid() {
-            println!("pid={pid}");
-        }
-        return Ok(());
+        return emit(
+            output,
+            &StartOutput {
+                vm_started: true,
+                backend,
+                detached: true,
+                socket: path_to_string(detached.socket_path()),
+                pid: detached.pid(),
+                waiting: None,
+            },
+        );
     }
 
-    println!("detached=false");
-    println!("socket={}", process.socket_path().display());
-    if let Some(pid) = process.pid() {
-        println!("pid={pid}");
-    }
-    println!("waiting=true");
-    println!("hint=press Ctrl+C to stop microVM");
-
-    tokio::select! {
-        _ = tokio::signal::ctrl_c() => {
-            let status = process.shutdown().await?;
-            match status {
-                Some(status) => println!("exit_status={status}"),
-                None => println!("exit_status=unknown"),
-            }
-        }
-        status = process.wait() => {
-            let status = status?;
-            match status {
-                Some(status) => println!("exit_status={status}"),
-                None => println!("exit_status=unknown"),
-            }
-        }
+    // Registered before reporting the VM as started so a supervisor's stop
+    // signal is never missed and always leads to a graceful shutdown.
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    emit(
+        output,
+        &StartOutput {
+            vm_started: true,
+            backend,
+            detached: false,
+            socket: path_to_string(process.socket_path()),
+            pid: process.pid(),
+            waiting: Some(true),
+        },
+    )?;
+    if matches!(output, OutputFormat::Text) {
+        println!("hint=press Ctrl+C to stop microVM");
     }
 
-    Ok(())
+    let status = tokio::select! {
+        _ = tokio::signal::ctrl_c() => process.shutdown().await?,
+        _ = terminate.recv() => process.shutdown().await?,
+        status = process.wait() => status?,
+    };
+    let exit_status = match status {
+        Some(status) => status.to_string(),
+        None => "unknown".to_owned(),
+    };
+
+    emit(output, &ExitOutput { exit_status })
+}
+
+async fn snapshot(
+    args: SnapshotArgs,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let vm = connect_vm(&args.socket_path)?;
+    let snapshot_path = path_to_string(&args.snapshot_path);
+    let mem_file_path = path_to_string(&args.mem_file_path);
+
+    vm.snapshot_and_resume(&snapshot_path, &mem_file_path, args.diff)
+        .await?;
+
+    emit(
+        output,
+        &SnapshotOutput {
+            snapshot_created: true,
+            snapshot_type: if args.diff { "diff" } else { "full" },
+            snapshot_path,
+            mem_file_path,
+        },
+    )
+}
+
+async fn restore(
+    args: RestoreArgs,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    firecracker::sdk::restore(
+        &args.socket_path,
+        types::SnapshotLoadParams {
+            snapshot_path: path_to_string(&args.snapshot_path),
+            mem_file_path: Some(path_to_string(&args.mem_file_path)),
+            mem_backend: None,
+            enable_diff_snapshots: None,
+            track_dirty_pages: None,
+            resume_vm: Some(args.resume),
+            network_overrides: vec![],
+        },
+    )
+    .await?;
+
+    emit(
+        output,
+        &RestoreOutput {
+            restored: true,
+            resumed: args.resume,
+            socket: path_to_string(&args.socket_path),
+        },
+    )
+}
+
+async fn pause(args: SocketArgs, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    connect_vm(&args.socket_path)?.pause().await?;
+    emit(
+        output,
+        &PauseOutput {
+            paused: true,
+            socket: path_to_string(&args.socket_path),
+        },
+    )
+}
+
+async fn resume(args: SocketArgs, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    connect_vm(&args.socket_path)?.resume().await?;
+    emit(
+        output,
+        &ResumeOutput {
+            resumed: true,
+            socket: path_to_string(&args.socket_path),
+        },
+    )
+}
+
+async fn shutdown(
+    args: ShutdownArgs,
+    output: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let socket_path = &args.socket.socket_path;
+    let vm = connect_vm(socket_path)?;
+    vm.shutdown_and_wait(Duration::from_secs(args.timeout_secs))
+        .await?;
+
+    emit(
+        output,
+        &ShutdownOutput {
+            shutdown: true,
+            socket: path_to_string(socket_path),
+        },
+    )
+}
+
+async fn inspect(args: SocketArgs, output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let vm = Vm::attach(&args.socket_path).await?;
+    let info = vm.describe().await?;
+    let drives = vm
+        .list_drives()
+        .await?
+        .into_iter()
+        .map(|drive| DriveOutput {
+            id: drive.drive_id,
+            path: drive.path_on_host,
+            socket: drive.socket,
+            read_only: drive.is_read_only.unwrap_or(false),
+            root: drive.is_root_device,
+        })
+        .collect();
+    let network_interfaces = vm
+        .list_network_interfaces()
+        .await?
+        .into_iter()
+        .map(|iface| NetOutput {
+            id: iface.iface_id,
+            tap: iface.host_dev_name,
+            mac: iface.guest_mac,
+        })
+        .collect();
+
+    emit(
+        output,
+        &InspectOutput {
+            id: info.id,
+            state: info.state,
+            vmm_version: info.vmm_version,
+            socket: path_to_string(&args.socket_path),
+            drives,
+            network_interfaces,
+        },
+    )
 }
 
 async fn spawn_process(
@@ -344,6 +720,7 @@ async fn spawn_process(
             };
             let mut builder = FirecrackerProcessBuilder::new(&firecracker_bin, &args.socket_path)
                 .no_seccomp(args.no_seccomp)
+                .boot_timer(args.boot_timer)
                 .socket_timeout(Duration::from_secs(args.socket_timeout_secs))
                 .socket_poll_interval(Duration::from_millis(args.socket_poll_interval_ms));
 
@@ -356,8 +733,20 @@ async fn spawn_process(
             if let Some(metrics_path) = &args.metrics_path {
                 builder = builder.metrics_path(metrics_path.clone());
             }
-            if let Some(log_level) = &args.log_level {
-                builder = builder.log_level(log_level.clone());
+            if let Some(log_level) = args.log_level {
+                builder = builder.log_level_typed(log_level);
+            }
+            if let Some(filter) = &args.seccomp_filter {
+                builder = builder.seccomp_filter(filter.clone());
+            }
+            if let Some(size) = args.http_api_max_payload_size {
+                builder = builder.http_api_max_payload_size(size);
+            }
+            if let Some(size) = args.mmds_size_limit {
+                builder = builder.mmds_size_limit(size);
+            }
+            if args.enable_pci {
+                builder = builder.enable_pci(true);
             }
 
             Ok(builder.spawn().await?)
@@ -407,6 +796,33 @@ async fn spawn_process(
             if args.no_seccomp {
                 builder = builder.firecracker_arg("--no-seccomp");
             }
+            // Firecracker loads the seccomp filter after pivot_root, so it has
+            // to be inside the chroot before the jailer is started.
+            if let Some(filter) = &args.seccomp_filter {
+                let chroot_root = builder.layout().chroot_root;
+                std::fs::create_dir_all(&chroot_root)?;
+                let filter =
+                    copy_to_chroot(&chroot_root, filter, file_name(filter)?, args.uid, args.gid)?;
+                builder = builder
+                    .firecracker_arg("--seccomp-filter")
+                    .firecracker_arg(filter.display().to_string());
+            }
+            if args.boot_timer {
+                builder = builder.firecracker_arg("--boot-timer");
+            }
+            if let Some(size) = args.http_api_max_payload_size {
+                builder = builder
+                    .firecracker_arg("--http-api-max-payload-size")
+                    .firecracker_arg(size.to_string());
+            }
+            if let Some(size) = args.mmds_size_limit {
+                builder = builder
+                    .firecracker_arg("--mmds-size-limit")
+                    .firecracker_arg(size.to_string());
+            }
+            if args.enable_pci {
+                builder = builder.firecracker_arg("--enable-pci");
+            }
             // log-path and metrics-path are resolved by Firecracker inside the
             // chroot, so use a fixed chroot-relative path and let the file be
             // created there.  The original host path is NOT accessible after
@@ -421,10 +837,10 @@ async fn spawn_process(
                     .firecracker_arg("--metrics-path")
                     .firecracker_arg("firecracker-metrics");
             }
-            if let Some(log_level) = &args.log_level {
+            if let Some(log_level) = args.log_level {
                 builder = builder
                     .firecracker_arg("--level")
-                    .firecracker_arg(log_level.clone());
+                    .firecracker_arg(log_level.to_string());
             }
 
             Ok(builder.spawn().await?)
@@ -434,8 +850,32 @@ async fn spawn_process(
 
 struct VmPaths {
     kernel: PathBuf,
-    rootfs: PathBuf,
     initrd: Option<PathBuf>,
+    drives: Vec<DriveSpec>,
+}
+
+/// Combine `--rootfs` and `--drive` flags into a single ordered drive list.
+fn collect_drives(args: &StartArgs) -> Result<Vec<DriveSpec>, Box<dyn std::error::Error>> {
+    let mut drives = Vec::with_capacity(args.drives.len() + 1);
+    if let Some(rootfs) = &args.rootfs {
+        drives.push(DriveSpec {
+            id: args.rootfs_id.clone(),
+            path: rootfs.clone(),
+            read_only: args.rootfs_read_only,
+            root: true,
+        });
+    }
+    drives.extend(args.drives.iter().cloned());
+
+    let root_count = drives.iter().filter(|d| d.root).count();
+    if root_count > 1 {
+        return Err(invalid_input(
+            "at most one drive may be marked root (`--rootfs` counts as a root drive)",
+        )
+        .into());
+    }
+
+    Ok(drives)
 }
 
 async fn configure_vm(
@@ -444,7 +884,7 @@ async fn configure_vm(
     vcpu_count: NonZeroU64,
     paths: &VmPaths,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    process
+    let mut builder = process
         .vm_builder()
         .boot_source(types::BootSource {
             kernel_image_path: path_to_string(&paths.kernel),
@@ -458,49 +898,47 @@ async fn configure_vm(
             track_dirty_pages: args.track_dirty_pages,
             cpu_template: None,
             huge_pages: None,
-        })
-        .drive(types::Drive {
-            drive_id: args.rootfs_id.clone(),
-            path_on_host: Some(path_to_string(&paths.rootfs)),
-            is_root_device: true,
-            is_read_only: Some(args.rootfs_read_only),
+        });
+
+    for drive in &paths.drives {
+        builder = builder.drive(types::Drive {
+            drive_id: drive.id.clone(),
+            path_on_host: Some(path_to_string(&drive.path)),
+            is_root_device: drive.root,
+            is_read_only: Some(drive.read_only),
             partuuid: None,
             cache_type: types::DriveCacheType::Unsafe,
             rate_limiter: None,
             io_engine: types::DriveIoEngine::Sync,
             socket: None,
-        })
-        .start()
-        .await?;
+        });
+    }
+
+    for net in &args.nets {
+        builder = builder.network_interface(types::NetworkInterface {
+            iface_id: net.id.clone(),
+            host_dev_name: net.tap.clone(),
+            guest_mac: net.mac.clone(),
+            rx_rate_limiter: None,
+            tx_rate_limiter: None,
+        });
+    }
+
+    builder.start().await?;
 
     Ok(())
 }
 
-/// Derive the chroot root directory from the jailer socket path.
-///
-/// Socket path format: `{chroot_base}/{exec_name}/{id}/root/run/firecracker.socket`
-/// We need:            `{chroot_base}/{exec_name}/{id}/root/`
-fn chroot_root_from_socket(socket_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    // Strip run/firecracker.socket → .../root
-    let root = socket_path
-        .parent() // .../root/run
-        .and_then(|p| p.parent()) // .../root
-        .ok_or_else(|| invalid_input("cannot derive chroot root from socket path"))?;
-    Ok(root.to_path_buf())
-}
-
-/// Copy a file into the chroot root directory and set ownership.
+/// Copy a file into the chroot root directory as `dest_name` and set ownership.
 /// Returns the chroot-relative path (e.g. `/vmlinux`).
 fn copy_to_chroot(
     chroot_root: &Path,
     source: &Path,
+    dest_name: &OsStr,
     uid: Option<u32>,
     gid: Option<u32>,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let file_name = source
-        .file_name()
-        .ok_or_else(|| invalid_input(&format!("path has no filename: {}", source.display())))?;
-    let dest = chroot_root.join(file_name);
+    let dest = chroot_root.join(dest_name);
     std::fs::copy(source, &dest).map_err(|e| {
         invalid_input(&format!(
             "failed to copy {} → {}: {e}",
@@ -511,40 +949,100 @@ fn copy_to_chroot(
     chown(&dest, uid, gid)
         .map_err(|e| invalid_input(&format!("failed to chown {}: {e}", dest.display())))?;
     // Return the chroot-relative path (Firecracker sees / as chroot root).
-    Ok(PathBuf::from("/").join(file_name))
+    Ok(PathBuf::from("/").join(dest_name))
 }
 
-/// Stage kernel, rootfs, and optionally initrd into the jailer chroot directory.
+fn file_name(path: &Path) -> Result<&OsStr, std::io::Error> {
+    path.file_name()
+        .ok_or_else(|| invalid_input(&format!("path has no filename: {}", path.display())))
+}
+
+/// Stage kernel, drives, and optionally initrd into the jailer chroot directory.
+///
+/// Drives are staged as `{drive_id}-{file_name}` so that drives sharing a
+/// file name do not overwrite each other. Destination names, including the
+/// seccomp filter's, are checked for collisions before anything is copied.
 fn stage_jailer_resources(
     chroot_root: &Path,
     args: &StartArgs,
+    drives: &[DriveSpec],
 ) -> Result<VmPaths, Box<dyn std::error::Error>> {
-    let kernel = copy_to_chroot(chroot_root, &args.kernel, args.uid, args.gid)?;
-    let rootfs = copy_to_chroot(chroot_root, &args.rootfs, args.uid, args.gid)?;
-    let initrd = match &args.initrd {
-        Some(initrd_path) => Some(copy_to_chroot(
+    let kernel_name = file_name(&args.kernel)?.to_owned();
+    let initrd_name = match &args.initrd {
+        Some(initrd_path) => Some(file_name(initrd_path)?.to_owned()),
+        None => None,
+    };
+    let drive_names = drives
+        .iter()
+        .map(|drive| {
+            let mut name = OsString::from(format!("{}-", drive.id));
+            name.push(file_name(&drive.path)?);
+            Ok(name)
+        })
+        .collect::<Result<Vec<_>, std::io::Error>>()?;
+
+    // The seccomp filter, if any, is already staged under its file name.
+    let filter_name = match &args.seccomp_filter {
+        Some(filter) => Some(file_name(filter)?.to_owned()),
+        None => None,
+    };
+    let mut seen = HashSet::new();
+    for name in filter_name
+        .iter()
+        .chain(std::iter::once(&kernel_name))
+        .chain(&initrd_name)
+        .chain(&drive_names)
+    {
+        if !seen.insert(name) {
+            return Err(invalid_input(&format!(
+                "two staged files would both be copied to /{} in the chroot",
+                name.to_string_lossy()
+            ))
+            .into());
+        }
+    }
+
+    let kernel = copy_to_chroot(chroot_root, &args.kernel, &kernel_name, args.uid, args.gid)?;
+    let drives = drives
+        .iter()
+        .zip(&drive_names)
+        .map(|(drive, name)| {
+            Ok(DriveSpec {
+                path: copy_to_chroot(chroot_root, &drive.path, name, args.uid, args.gid)?,
+                ..drive.clone()
+            })
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    let initrd = match (&args.initrd, &initrd_name) {
+        (Some(initrd_path), Some(name)) => Some(copy_to_chroot(
             chroot_root,
             initrd_path,
+            name,
             args.uid,
             args.gid,
         )?),
-        None => None,
+        _ => None,
     };
     Ok(VmPaths {
         kernel,
-        rootfs,
         initrd,
+        drives,
     })
 }
 
-fn platform() {
+fn platform(output: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
     let os = std::env::consts::OS;
     let arch = std::env::consts::ARCH;
     let supported = os == "linux" && (arch == "x86_64" || arch == "aarch64");
 
-    println!("os={os}");
-    println!("arch={arch}");
-    println!("bundled_release_supported={supported}");
+    emit(
+        output,
+        &PlatformOutput {
+            os,
+            arch,
+            bundled_release_supported: supported,
+        },
+    )
 }
 
 fn build_runtime_options(args: &RuntimeArgs) -> BundledRuntimeOptions {
@@ -566,6 +1064,75 @@ fn build_runtime_options(args: &RuntimeArgs) -> BundledRuntimeOptions {
     opts
 }
 
+/// Parse a `--drive` value: `id=...,path=...[,readonly=true|false][,root=true|false]`.
+fn parse_drive_spec(value: &str) -> Result<DriveSpec, String> {
+    let mut id = None;
+    let mut path = None;
+    let mut read_only = false;
+    let mut root = false;
+
+    for (key, val) in parse_key_values(value)? {
+        match key {
+            "id" => id = Some(val.to_owned()),
+            "path" => path = Some(PathBuf::from(val)),
+            "readonly" => read_only = parse_bool_field(key, val)?,
+            "root" => root = parse_bool_field(key, val)?,
+            other => return Err(format!("unknown --drive field: {other}")),
+        }
+    }
+
+    Ok(DriveSpec {
+        id: id.ok_or("--drive requires `id=...`")?,
+        path: path.ok_or("--drive requires `path=...`")?,
+        read_only,
+        root,
+    })
+}
+
+/// Parse a `--net` value: `id=...,tap=...[,mac=...]`.
+fn parse_net_spec(value: &str) -> Result<NetSpec, String> {
+    let mut id = None;
+    let mut tap = None;
+    let mut mac = None;
+
+    for (key, val) in parse_key_values(value)? {
+        match key {
+            "id" => id = Some(val.to_owned()),
+            "tap" => tap = Some(val.to_owned()),
+            "mac" => mac = Some(val.to_owned()),
+            other => return Err(format!("unknown --net field: {other}")),
+        }
+    }
+
+    Ok(NetSpec {
+        id: id.ok_or("--net requires `id=...`")?,
+        tap: tap.ok_or("--net requires `tap=...`")?,
+        mac,
+    })
+}
+
+fn parse_key_values(value: &str) -> Result<Vec<(&str, &str)>, String> {
+    value
+        .split(',')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.split_once('=')
+                .ok_or_else(|| format!("expected `key=value`, got `{part}`"))
+        })
+        .collect()
+}
+
+fn parse_bool_field(key: &str, value: &str) -> Result<bool, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{key}` must be `true` or `false`, got `{value}`"))
+}
+
+/// Build a [`Vm`] handle for a microVM already running behind `socket_path`.
+fn connect_vm(socket_path: &Path) -> Result<Vm, Box<dyn std::error::Error>> {
+    Ok(Vm::from_client(connection::try_connect(socket_path)?))
+}
+
 fn path_to_string(path: &Path) -> String {
     path.display().to_string()
 }
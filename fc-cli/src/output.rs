@@ -0,0 +1,42 @@
+//! Command result rendering for `--output text|json`.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Output format for command results.
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// `key=value` lines.
+    #[default]
+    Text,
+    /// One JSON object per result, each on its own line (NDJSON).
+    Json,
+}
+
+/// Print a flat command result in the selected format.
+///
+/// In text mode every field is printed as a `key=value` line in declaration
+/// order; `None` fields should be skipped via `skip_serializing_if`. Lists
+/// and nested objects are printed as compact JSON values.
+pub fn emit<T: Serialize>(
+    format: OutputFormat,
+    result: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string(result)?),
+        OutputFormat::Text => {
+            let Value::Object(fields) = serde_json::to_value(result)? else {
+                return Err("command result must serialize to an object".into());
+            };
+            for (key, value) in fields {
+                match value {
+                    Value::Null => {}
+                    Value::String(s) => println!("{key}={s}"),
+                    other => println!("{key}={other}"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,44 @@
+pub mod overlay;
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::num::NonZeroU64;
 use std::path::Path;
+use std::time::Duration;
 
 use fc_api::Client;
 use fc_api::types::{
-    Balloon, BootSource, CpuConfig, Drive, EntropyDevice, FullVmConfiguration, Logger,
-    MachineConfiguration, MemoryHotplugConfig, Metrics, MmdsConfig, NetworkInterface, Pmem,
-    SerialDevice, Vsock,
+    Balloon, BootSource, CpuConfig, Drive, DriveIoEngine, EntropyDevice, FullVmConfiguration,
+    InstanceActionInfo, InstanceActionInfoActionType, Logger, MachineConfiguration,
+    MachineConfigurationHugePages, MemoryHotplugConfig, Metrics, MmdsConfig, MmdsConfigVersion,
+    NetworkInterface, Pmem, RateLimiter, SerialDevice, TokenBucket, Vsock,
 };
 
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
 use crate::error::{Error, Result};
+use crate::process::LogLevel;
+use crate::trace::{traced, traced_id};
+use crate::version::Feature;
 use crate::vm::Vm;
 
+/// virtio-pmem backing files must be a multiple of this size.
+const PMEM_ALIGNMENT: u64 = 2 * 1024 * 1024;
+
+/// Oldest host kernel Firecracker supports the io_uring (`Async`) engine on.
+const IO_URING_MIN_KERNEL: (u64, u64, u64) = (5, 10, 51);
+
+/// Default link-local address of the MMDS network stack.
+const DEFAULT_MMDS_IPV4_ADDRESS: &str = "169.254.169.254";
+
 /// Pre-boot VM configuration builder.
 ///
 /// Accumulates configuration and sends it to Firecracker upon [`start()`](Self::start).
 ///
+/// Neither the builder nor the resulting [`Vm`] ever removes the API socket;
+/// socket cleanup is done only by [`FirecrackerProcess`](crate::FirecrackerProcess).
+///
 /// # Required Configuration
 ///
 /// - [`boot_source()`](Self::boot_source) — kernel image path (required)
@@ -72,6 +97,13 @@ pub struct VmBuilder {
     mmds_data: Option<serde_json::Map<String, serde_json::Value>>,
     logger: Option<Logger>,
     metrics: Option<Metrics>,
+    apply_order: Vec<ConfigStage>,
+    local_checks: bool,
+    root_with_initrd: bool,
+    vsock_conflict: bool,
+    start_timeout: Option<Duration>,
+    wait_for_boot: Option<Duration>,
+    expected_id: Option<String>,
 }
 
 impl VmBuilder {
@@ -95,9 +127,34 @@ impl VmBuilder {
             mmds_data: None,
             logger: None,
             metrics: None,
+            apply_order: ConfigStage::DEFAULT_ORDER.to_vec(),
+            local_checks: true,
+            root_with_initrd: false,
+            vsock_conflict: false,
+            start_timeout: None,
+            wait_for_boot: None,
+            expected_id: None,
         }
     }
 
+    /// Create a new builder for a socket owned by another, long-lived process.
+    ///
+    /// Behaves like [`new()`](Self::new); the name documents that the caller
+    /// does not own the socket. Pairs with [`Vm::attach()`].
+    pub fn with_existing_socket_no_cleanup(socket_path: impl AsRef<Path>) -> Self {
+        Self::new(socket_path)
+    }
+
+    /// Create a new builder using the cached client for `socket_path`.
+    ///
+    /// See [`SocketClientCache`](crate::connection::SocketClientCache).
+    pub fn new_cached(
+        socket_path: impl AsRef<Path>,
+        cache: &crate::connection::SocketClientCache,
+    ) -> Result<Self> {
+        Ok(Self::with_client(cache.get(socket_path)?))
+    }
+
     /// Create a new builder using an existing API client.
     pub fn with_client(client: Client) -> Self {
         Self {
@@ -117,6 +174,13 @@ impl VmBuilder {
             mmds_data: None,
             logger: None,
             metrics: None,
+            apply_order: ConfigStage::DEFAULT_ORDER.to_vec(),
+            local_checks: true,
+            root_with_initrd: false,
+            vsock_conflict: false,
+            start_timeout: None,
+            wait_for_boot: None,
+            expected_id: None,
         }
     }
 
@@ -128,6 +192,19 @@ impl VmBuilder {
         Self::from_config_with_client(client, config)
     }
 
+    /// Create a new builder pre-populated from a Firecracker JSON config file.
+    ///
+    /// Accepts the `--config-file` format, including files written by
+    /// [`Vm::export_config_to_file()`](crate::Vm::export_config_to_file).
+    pub fn from_json_file(
+        socket_path: impl AsRef<Path>,
+        config_path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let json = std::fs::read(config_path)?;
+        let config = serde_json::from_slice(&json)?;
+        Ok(Self::from_config(socket_path, config))
+    }
+
     /// Create a new builder pre-populated from a [`FullVmConfiguration`] using an existing client.
     pub fn from_config_with_client(client: Client, config: FullVmConfiguration) -> Self {
         Self {
@@ -147,6 +224,13 @@ impl VmBuilder {
             mmds_data: None,
             logger: config.logger,
             metrics: config.metrics,
+            apply_order: ConfigStage::DEFAULT_ORDER.to_vec(),
+            local_checks: true,
+            root_with_initrd: false,
+            vsock_conflict: false,
+            start_timeout: None,
+            wait_for_boot: None,
+            expected_id: None,
         }
     }
 
@@ -162,6 +246,26 @@ impl VmBuilder {
         self
     }
 
+    /// Boot `kernel` with `initrd` as the root filesystem.
+    ///
+    /// Sets the boot source; [`start()`](Self::start) then rejects any drive
+    /// or pmem device marked as root, since Firecracker would also point the
+    /// kernel at it. Call
+    /// [`allow_root_drive_with_initrd()`](Self::allow_root_drive_with_initrd)
+    /// if the initrd is meant to hand over to that root device.
+    pub fn boot_from_initrd(
+        self,
+        kernel: impl AsRef<Path>,
+        initrd: impl AsRef<Path>,
+        boot_args: Option<String>,
+    ) -> Self {
+        self.boot_source(BootSource {
+            kernel_image_path: kernel.as_ref().display().to_string(),
+            initrd_path: Some(initrd.as_ref().display().to_string()),
+            boot_args,
+        })
+    }
+
     /// Set the machine configuration (vCPU count, memory size, etc.).
     ///
     /// **Required** — the VM cannot start without machine configuration.
@@ -170,6 +274,23 @@ impl VmBuilder {
         self
     }
 
+    /// Set the vCPU count and memory size, leaving the rest of the machine
+    /// configuration at its defaults.
+    ///
+    /// SMT and dirty page tracking are disabled and no CPU template or huge
+    /// pages are used. Use [`machine_config()`](Self::machine_config) to set
+    /// those.
+    pub fn machine_config_simple(self, vcpu_count: NonZeroU64, mem_size_mib: i64) -> Self {
+        self.machine_config(MachineConfiguration {
+            vcpu_count,
+            mem_size_mib,
+            smt: false,
+            track_dirty_pages: false,
+            cpu_template: None,
+            huge_pages: None,
+        })
+    }
+
     // =========================================================================
     // Optional Configuration
     // =========================================================================
@@ -180,12 +301,31 @@ impl VmBuilder {
         self
     }
 
+    /// Set CPU configuration from a custom CPU template JSON file, e.g. one
+    /// produced by `cpu-template-helper`.
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read and [`Error::Serde`]
+    /// if it is not a valid template.
+    pub fn cpu_config_from_file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        Ok(self.cpu_config(serde_json::from_slice(&json)?))
+    }
+
     /// Add a block device (drive).
     pub fn drive(mut self, drive: Drive) -> Self {
         self.drives.push(drive);
         self
     }
 
+    /// Add a block device using the best IO engine the host supports.
+    ///
+    /// Overrides `drive.io_engine` with [`detect_io_engine()`].
+    pub fn drive_auto_io(mut self, mut drive: Drive) -> Self {
+        drive.io_engine = detect_io_engine();
+        self.drives.push(drive);
+        self
+    }
+
     /// Add a root drive (convenience method that sets `is_root_device` to true).
     pub fn root_drive(mut self, mut drive: Drive) -> Self {
         drive.is_root_device = true;
@@ -193,42 +333,163 @@ impl VmBuilder {
         self
     }
 
+    /// Add a writable copy-on-write overlay of `base` as the root drive
+    /// `drive_id`.
+    ///
+    /// Creates `overlay` via [`overlay::create_overlay()`] and attaches it
+    /// read-write; `base` itself is only read and never attached, so it can be
+    /// shared by many microVMs.
+    pub fn root_drive_overlay(
+        self,
+        drive_id: impl Into<String>,
+        base: impl AsRef<Path>,
+        overlay: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let overlay = overlay.as_ref();
+        overlay::create_overlay(base.as_ref(), overlay)?;
+        Ok(self.root_drive(Drive {
+            drive_id: drive_id.into(),
+            path_on_host: Some(overlay.display().to_string()),
+            is_root_device: true,
+            is_read_only: Some(false),
+            cache_type: Default::default(),
+            io_engine: DriveIoEngine::Sync,
+            partuuid: None,
+            rate_limiter: None,
+            socket: None,
+        }))
+    }
+
     /// Add a virtio-pmem persistent memory device.
     pub fn pmem(mut self, pmem: Pmem) -> Self {
         self.pmem_devices.push(pmem);
         self
     }
 
+    /// Add a non-root virtio-pmem device backed by the file at `path`.
+    ///
+    /// The file is checked for existence and 2 MiB alignment when the
+    /// configuration is applied, unless local checks are skipped.
+    pub fn pmem_from_file(
+        self,
+        id: impl Into<String>,
+        path: impl AsRef<Path>,
+        read_only: bool,
+    ) -> Self {
+        self.pmem(Pmem {
+            id: id.into(),
+            path_on_host: path.as_ref().to_string_lossy().into_owned(),
+            read_only: Some(read_only),
+            root_device: None,
+        })
+    }
+
     /// Add a network interface.
     pub fn network_interface(mut self, iface: NetworkInterface) -> Self {
         self.network_interfaces.push(iface);
         self
     }
 
+    /// Add a network interface backed by the host tap device `tap`.
+    ///
+    /// `tap` must outlive the microVM; Firecracker only opens the device by
+    /// name when the interface is configured.
+    #[cfg(feature = "host-net")]
+    pub fn network_interface_with_tap(
+        self,
+        iface_id: impl Into<String>,
+        tap: &crate::net::TapGuard,
+    ) -> Self {
+        self.network_interface(NetworkInterface {
+            iface_id: iface_id.into(),
+            host_dev_name: tap.name().to_owned(),
+            guest_mac: None,
+            rx_rate_limiter: None,
+            tx_rate_limiter: None,
+        })
+    }
+
     /// Configure the balloon device for memory ballooning.
     pub fn balloon(mut self, balloon: Balloon) -> Self {
         self.balloon = Some(balloon);
         self
     }
 
+    /// Configure the balloon device from a [`BalloonBuilder`].
+    pub fn balloon_with(self, balloon: BalloonBuilder) -> Self {
+        self.balloon(balloon.build())
+    }
+
     /// Configure a vsock device for host-guest communication.
+    ///
+    /// Firecracker supports a single vsock device. Calling this again with
+    /// different settings makes [`start()`](Self::start) fail instead of
+    /// silently dropping the first device; use [`try_vsock()`](Self::try_vsock)
+    /// to detect the mistake immediately.
     pub fn vsock(mut self, vsock: Vsock) -> Self {
+        if let Some(existing) = &self.vsock
+            && !same_vsock(existing, &vsock)
+        {
+            self.vsock_conflict = true;
+        }
         self.vsock = Some(vsock);
         self
     }
 
+    /// Like [`vsock()`](Self::vsock), but fails with [`Error::InvalidConfig`]
+    /// if a vsock device is already configured.
+    pub fn try_vsock(self, vsock: Vsock) -> Result<Self> {
+        if let Some(existing) = &self.vsock {
+            return Err(Error::InvalidConfig(format!(
+                "vsock already configured (guest_cid {}, uds_path {}); \
+                 Firecracker supports a single vsock device",
+                existing.guest_cid, existing.uds_path
+            )));
+        }
+        Ok(self.vsock(vsock))
+    }
+
     /// Configure an entropy device for high-quality random data.
+    ///
+    /// Firecracker supports a single entropy device; a later call replaces
+    /// the earlier one.
     pub fn entropy(mut self, entropy: EntropyDevice) -> Self {
         self.entropy = Some(entropy);
         self
     }
 
+    /// Configure an entropy device limited by `rate_limiter`.
+    ///
+    /// Each token bucket must have a non-zero `size` and `refill_time`; this
+    /// is checked in [`start()`](Self::start).
+    pub fn entropy_with_rate(self, rate_limiter: RateLimiter) -> Self {
+        self.entropy(EntropyDevice {
+            rate_limiter: Some(rate_limiter),
+        })
+    }
+
     /// Configure serial console output redirection.
     pub fn serial(mut self, serial: SerialDevice) -> Self {
         self.serial = Some(serial);
         self
     }
 
+    /// Write the guest's serial console output to `path`, a file or named
+    /// pipe on the host.
+    pub fn serial_to_file(self, path: impl AsRef<Path>) -> Self {
+        self.serial(SerialDevice {
+            serial_out_path: Some(path.as_ref().display().to_string()),
+        })
+    }
+
+    /// Serial console output path, if one was configured.
+    pub fn serial_out_path(&self) -> Option<&Path> {
+        self.serial
+            .as_ref()
+            .and_then(|serial| serial.serial_out_path.as_deref())
+            .map(Path::new)
+    }
+
     /// Configure virtio-mem hotpluggable memory.
     pub fn memory_hotplug(mut self, memory_hotplug: MemoryHotplugConfig) -> Self {
         self.memory_hotplug = Some(memory_hotplug);
@@ -241,10 +502,29 @@ impl VmBuilder {
         self
     }
 
+    /// Configure MMDS V1 (unauthenticated) on the given network interfaces.
+    ///
+    /// Each id must match an interface added via
+    /// [`network_interface()`](Self::network_interface); this is checked in
+    /// [`start()`](Self::start).
+    pub fn mmds_v1(self, interface_ids: Vec<String>) -> Self {
+        self.mmds_config(mmds_config(MmdsConfigVersion::V1, interface_ids))
+    }
+
+    /// Configure MMDS V2 (session token based) on the given network interfaces.
+    ///
+    /// Each id must match an interface added via
+    /// [`network_interface()`](Self::network_interface); this is checked in
+    /// [`start()`](Self::start).
+    pub fn mmds_v2(self, interface_ids: Vec<String>) -> Self {
+        self.mmds_config(mmds_config(MmdsConfigVersion::V2, interface_ids))
+    }
+
     /// Set the initial MMDS data store contents.
     ///
-    /// The MMDS config must also be set via [`mmds_config()`](Self::mmds_config) for this
-    /// to take effect. The data is applied after the MMDS config during [`start()`](Self::start).
+    /// The data is applied after the MMDS config during [`start()`](Self::start)
+    /// and is the same for V1 and V2. Without an MMDS config it is still
+    /// stored (and readable via [`Vm::get_mmds()`]) but the guest cannot reach it.
     pub fn mmds_data(mut self, data: serde_json::Map<String, serde_json::Value>) -> Self {
         self.mmds_data = Some(data);
         self
@@ -256,18 +536,171 @@ impl VmBuilder {
         self
     }
 
+    /// Set the logger's level, keeping any other logger settings.
+    ///
+    /// If no logger is configured yet, one is created that logs to
+    /// Firecracker's standard output.
+    pub fn log_level_typed(mut self, level: LogLevel) -> Self {
+        match &mut self.logger {
+            Some(logger) => logger.level = level.into(),
+            None => {
+                self.logger = Some(Logger {
+                    level: level.into(),
+                    log_path: None,
+                    module: None,
+                    show_level: false,
+                    show_log_origin: false,
+                });
+            }
+        }
+        self
+    }
+
+    /// Write Firecracker's log to `path`, keeping any other logger settings.
+    ///
+    /// If no logger is configured yet, one is created at [`LogLevel::Info`].
+    pub fn logger_to_file(mut self, path: impl AsRef<Path>) -> Self {
+        let log_path = Some(path.as_ref().display().to_string());
+        match &mut self.logger {
+            Some(logger) => logger.log_path = log_path,
+            None => {
+                self.logger = Some(Logger {
+                    level: LogLevel::Info.into(),
+                    log_path,
+                    module: None,
+                    show_level: false,
+                    show_log_origin: false,
+                });
+            }
+        }
+        self
+    }
+
     /// Configure metrics output.
     pub fn metrics(mut self, metrics: Metrics) -> Self {
         self.metrics = Some(metrics);
         self
     }
 
+    /// Flush metrics to `path`.
+    pub fn metrics_to_file(self, path: impl AsRef<Path>) -> Self {
+        self.metrics(Metrics {
+            metrics_path: path.as_ref().display().to_string(),
+        })
+    }
+
+    /// Skip checks against the host (pmem backing files and io_uring support).
+    ///
+    /// Use this when the paths are only meaningful to the Firecracker process
+    /// (e.g. inside a jailer chroot) rather than to the caller.
+    pub fn skip_local_checks(mut self) -> Self {
+        self.local_checks = false;
+        self
+    }
+
+    /// Allow a root drive or pmem device alongside an initrd.
+    ///
+    /// By default [`start()`](Self::start) rejects the combination; see
+    /// [`boot_from_initrd()`](Self::boot_from_initrd).
+    pub fn allow_root_drive_with_initrd(mut self) -> Self {
+        self.root_with_initrd = true;
+        self
+    }
+
+    /// Override the order in which configuration stages are applied.
+    ///
+    /// Logger and metrics are always applied first. `order` must list every
+    /// [`ConfigStage`] exactly once; the default is
+    /// [`ConfigStage::DEFAULT_ORDER`].
+    pub fn apply_order(mut self, order: Vec<ConfigStage>) -> Self {
+        self.apply_order = order;
+        self
+    }
+
+    /// Bound the whole boot sequence (configuration requests and
+    /// InstanceStart) of [`start()`](Self::start) and
+    /// [`start_with_retry()`](Self::start_with_retry).
+    ///
+    /// On expiry the boot fails with [`Error::Timeout`]; the Firecracker
+    /// process is left running for the caller to kill. No timeout by default.
+    pub fn start_timeout(mut self, timeout: Duration) -> Self {
+        self.start_timeout = Some(timeout);
+        self
+    }
+
+    /// Make [`start()`](Self::start) wait up to `timeout` for the instance to
+    /// report `Running` after InstanceStart is accepted.
+    ///
+    /// Fails with [`Error::Timeout`] if the state is not reached in time, and
+    /// with [`Error::ProcessExited`] if the API stops answering, e.g. because
+    /// the guest crashed Firecracker during boot. Not set by default, in
+    /// which case `start()` returns once the action is accepted.
+    pub fn wait_for_boot(mut self, timeout: Duration) -> Self {
+        self.wait_for_boot = Some(timeout);
+        self
+    }
+
+    /// Check that the instance behind the socket has id `id` before sending
+    /// any configuration.
+    ///
+    /// The id is fixed when Firecracker starts, from `--id` (see
+    /// [`FirecrackerProcessBuilder::id()`](crate::FirecrackerProcessBuilder::id))
+    /// or the jailer id, and cannot be set through the API. With this set,
+    /// [`start()`](Self::start) and [`configure()`](Self::configure) first
+    /// send `GET /` and fail with [`Error::Other`] on a mismatch, guarding
+    /// against connecting to the wrong socket in a shared directory. Not
+    /// captured by [`template()`](Self::template).
+    pub fn expected_id(mut self, id: impl Into<String>) -> Self {
+        self.expected_id = Some(id.into());
+        self
+    }
+
+    // =========================================================================
+    // Templates
+    // =========================================================================
+
+    /// Capture this builder's configuration as a reusable [`VmTemplate`].
+    pub fn template(&self) -> VmTemplate {
+        VmTemplate {
+            boot_source: self.boot_source.clone(),
+            machine_config: self.machine_config.clone(),
+            cpu_config: self.cpu_config.clone(),
+            drives: self.drives.clone(),
+            pmem_devices: self.pmem_devices.clone(),
+            network_interfaces: self.network_interfaces.clone(),
+            balloon: self.balloon.clone(),
+            vsock: self.vsock.clone(),
+            entropy: self.entropy.clone(),
+            serial: self.serial.clone(),
+            memory_hotplug: self.memory_hotplug.clone(),
+            mmds_config: self.mmds_config.clone(),
+            mmds_data: self.mmds_data.clone(),
+            logger: self.logger.clone(),
+            metrics: self.metrics.clone(),
+            apply_order: self.apply_order.clone(),
+            local_checks: self.local_checks,
+            root_with_initrd: self.root_with_initrd,
+            vsock_conflict: self.vsock_conflict,
+            start_timeout: self.start_timeout,
+            wait_for_boot: self.wait_for_boot,
+        }
+    }
+
+    /// Create a builder with the same configuration, connected to `socket_path`.
+    ///
+    /// Fails if no API client can be created for the socket.
+    pub fn try_clone(&self, socket_path: impl AsRef<Path>) -> Result<Self> {
+        let client = crate::connection::try_connect(socket_path)?;
+        Ok(self.template().apply_to(Self::with_client(client)))
+    }
+
     // =========================================================================
     // Build and Start
     // =========================================================================
 
     /// Apply all configuration and start the microVM.
     ///
+    /// Equivalent to [`configure()`](Self::configure) followed by [`Vm::start()`].
     /// Returns a [`Vm`] handle for post-boot operations.
     ///
     /// # Errors
@@ -275,146 +708,1180 @@ impl VmBuilder {
     /// Returns an error if:
     /// - `boot_source` is not configured
     /// - `machine_config` is not configured
+    /// - The configuration fails local validation (e.g. MMDS references an
+    ///   unknown network interface)
+    /// - The configuration uses a [`Feature`] the Firecracker instance is too
+    ///   old for (e.g. balloon free page reporting), checked before any
+    ///   configuration is sent
     /// - Any API call fails
+    /// - The [start timeout](Self::start_timeout) expires
+    /// - With [`wait_for_boot()`](Self::wait_for_boot), the instance does not
+    ///   reach `Running` in time or Firecracker exits
     pub async fn start(self) -> Result<Vm> {
+        self.boot(RetryPolicy::NONE).await
+    }
+
+    /// Apply all configuration without starting the microVM.
+    ///
+    /// The returned [`Vm`] is still pre-boot: pre-boot-only operations such as
+    /// [`Vm::update_machine_config()`] remain valid until [`Vm::start()`] is
+    /// called. This is useful for staged boots or attaching a debugger first.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`start()`](Self::start), except that the InstanceStart action
+    /// is not sent.
+    pub async fn configure(self) -> Result<Vm> {
+        self.configure_with(RetryPolicy::NONE).await
+    }
+
+    /// Like [`start()`](Self::start), retrying configuration requests that fail
+    /// to reach the API.
+    ///
+    /// Only the pre-boot configuration PUTs are retried, and only when the
+    /// error is [retryable](Error::is_retryable) (e.g. connection refused
+    /// while Firecracker is still coming up, or reset on the first request).
+    /// The InstanceStart action is sent exactly once.
+    pub async fn start_with_retry(self, policy: RetryPolicy) -> Result<Vm> {
+        self.boot(policy).await
+    }
+
+    /// Like [`start()`](Self::start), returning [`Error::Cancelled`] as soon
+    /// as `token` is cancelled.
+    ///
+    /// Requests already sent are not undone; the Firecracker process is left
+    /// for the caller to kill.
+    pub async fn start_cancellable(self, token: &CancellationToken) -> Result<Vm> {
+        crate::vm::cancellable(token, self.boot(RetryPolicy::NONE)).await
+    }
+
+    /// Send every configuration request, carrying on past failures, and
+    /// report the outcome of each.
+    ///
+    /// A diagnostic aid for finding every request Firecracker rejects in one
+    /// pass, not a way to boot: results are returned per request in
+    /// [apply order](Self::apply_order), so a stage with several devices
+    /// (e.g. [`ConfigStage::Drives`]) appears once per device, in the order
+    /// the devices were added. A missing boot source or machine
+    /// configuration is reported as [`Error::MissingConfig`] for its stage.
+    ///
+    /// Local validation, the [instance id check](Self::expected_id), the
+    /// version check and the logger and metrics requests are skipped, and
+    /// the InstanceStart action is never sent.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn example(builder: fc_sdk::VmBuilder) {
+    /// for (stage, result) in builder.apply_all_collect().await {
+    ///     if let Err(err) = result {
+    ///         eprintln!("{stage:?}: {err}");
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub async fn apply_all_collect(&self) -> Vec<(ConfigStage, Result<()>)> {
+        let mut results = Vec::new();
+        for stage in &self.apply_order {
+            // Failures are recorded in `results`, never returned.
+            self.apply_stage(*stage, RetryPolicy::NONE, &mut Some(&mut results))
+                .await
+                .ok();
+        }
+        results
+    }
+
+    async fn boot(self, policy: RetryPolicy) -> Result<Vm> {
+        let start_timeout = self.start_timeout;
+        let wait_for_boot = self.wait_for_boot;
+        let boot = async {
+            let vm = self.configure_with(policy).await?;
+            vm.start().await?;
+            if let Some(timeout) = wait_for_boot {
+                vm.wait_for_boot(timeout).await?;
+            }
+            Ok(vm)
+        };
+        match start_timeout {
+            Some(limit) => tokio::time::timeout(limit, boot)
+                .await
+                .map_err(|_| Error::Timeout("microVM to start"))?,
+            None => boot.await,
+        }
+    }
+
+    async fn configure_with(self, policy: RetryPolicy) -> Result<Vm> {
+        self.validate()?;
+        let features = self.required_features();
+
+        if self.boot_source.is_none() {
+            return Err(Error::MissingConfig("boot_source"));
+        }
+        if self.machine_config.is_none() {
+            return Err(Error::MissingConfig("machine_config"));
+        }
+        let client = &self.client;
+
+        if let Some(expected) = &self.expected_id {
+            let info = policy
+                .run(|| traced("describe_instance", client.describe_instance().send()))
+                .await?;
+            if info.id != *expected {
+                return Err(Error::Other(format!(
+                    "connected to instance {:?}, expected {expected:?}",
+                    info.id
+                )));
+            }
+        }
+
+        if !features.is_empty() {
+            let version = policy
+                .run(|| {
+                    traced(
+                        "get_firecracker_version",
+                        client.get_firecracker_version().send(),
+                    )
+                })
+                .await?;
+            let version = crate::vm::firecracker_semver(&version.firecracker_version)?;
+            check_features(&features, version)?;
+        }
+
+        // Apply logger first (if configured) — must be done before other config
+        if let Some(logger) = &self.logger {
+            policy
+                .run(|| {
+                    traced(
+                        "put_logger",
+                        client.put_logger().body(logger.clone()).send(),
+                    )
+                })
+                .await?;
+        }
+
+        // Apply metrics (if configured) — must be done before other config
+        if let Some(metrics) = &self.metrics {
+            policy
+                .run(|| {
+                    traced(
+                        "put_metrics",
+                        client.put_metrics().body(metrics.clone()).send(),
+                    )
+                })
+                .await?;
+        }
+
+        for stage in &self.apply_order {
+            self.apply_stage(*stage, policy, &mut None).await?;
+        }
+
+        Ok(Vm::new(self.client))
+    }
+
+    /// Send the requests of one configuration stage.
+    ///
+    /// With `results` set, every request is attempted and its outcome
+    /// recorded there; otherwise the first failure is returned.
+    async fn apply_stage(
+        &self,
+        stage: ConfigStage,
+        policy: RetryPolicy,
+        results: &mut Option<&mut Vec<(ConfigStage, Result<()>)>>,
+    ) -> Result<()> {
+        let client = &self.client;
+        match stage {
+            ConfigStage::BootSource => {
+                let Some(boot_source) = &self.boot_source else {
+                    let missing = Err::<(), _>(Error::MissingConfig("boot_source"));
+                    report(results, stage, missing)?;
+                    return Ok(());
+                };
+                report(
+                    results,
+                    stage,
+                    policy
+                        .run(|| {
+                            traced(
+                                "put_guest_boot_source",
+                                client
+                                    .put_guest_boot_source()
+                                    .body(boot_source.clone())
+                                    .send(),
+                            )
+                        })
+                        .await,
+                )?;
+            }
+            ConfigStage::MachineConfig => {
+                let Some(machine_config) = &self.machine_config else {
+                    let missing = Err::<(), _>(Error::MissingConfig("machine_config"));
+                    report(results, stage, missing)?;
+                    return Ok(());
+                };
+                report(
+                    results,
+                    stage,
+                    policy
+                        .run(|| {
+                            traced(
+                                "put_machine_configuration",
+                                client
+                                    .put_machine_configuration()
+                                    .body(machine_config.clone())
+                                    .send(),
+                            )
+                        })
+                        .await,
+                )?;
+            }
+            ConfigStage::CpuConfig => {
+                if let Some(cpu_config) = &self.cpu_config {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_cpu_configuration",
+                                    client
+                                        .put_cpu_configuration()
+                                        .body(cpu_config.clone())
+                                        .send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Drives => {
+                for drive in &self.drives {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced_id(
+                                    "put_guest_drive_by_id",
+                                    &drive.drive_id,
+                                    client
+                                        .put_guest_drive_by_id()
+                                        .drive_id(&drive.drive_id)
+                                        .body(drive.clone())
+                                        .send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Pmem => {
+                for pmem in &self.pmem_devices {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced_id(
+                                    "put_guest_pmem_by_id",
+                                    &pmem.id,
+                                    client
+                                        .put_guest_pmem_by_id()
+                                        .id(&pmem.id)
+                                        .body(pmem.clone())
+                                        .send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Network => {
+                for iface in &self.network_interfaces {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced_id(
+                                    "put_guest_network_interface_by_id",
+                                    &iface.iface_id,
+                                    client
+                                        .put_guest_network_interface_by_id()
+                                        .iface_id(&iface.iface_id)
+                                        .body(iface.clone())
+                                        .send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Balloon => {
+                if let Some(balloon) = &self.balloon {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_balloon",
+                                    client.put_balloon().body(balloon.clone()).send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Vsock => {
+                if let Some(vsock) = &self.vsock {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_guest_vsock",
+                                    client.put_guest_vsock().body(vsock.clone()).send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Entropy => {
+                if let Some(entropy) = &self.entropy {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_entropy_device",
+                                    client.put_entropy_device().body(entropy.clone()).send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Serial => {
+                if let Some(serial) = &self.serial {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_serial_device",
+                                    client.put_serial_device().body(serial.clone()).send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::MemoryHotplug => {
+                if let Some(memory_hotplug) = &self.memory_hotplug {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_memory_hotplug",
+                                    client
+                                        .put_memory_hotplug()
+                                        .body(memory_hotplug.clone())
+                                        .send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+            }
+            ConfigStage::Mmds => {
+                if let Some(mmds_config) = &self.mmds_config {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced(
+                                    "put_mmds_config",
+                                    client.put_mmds_config().body(mmds_config.clone()).send(),
+                                )
+                            })
+                            .await,
+                    )?;
+                }
+                if let Some(mmds_data) = &self.mmds_data {
+                    report(
+                        results,
+                        stage,
+                        policy
+                            .run(|| {
+                                traced("put_mmds", client.put_mmds().body(mmds_data.clone()).send())
+                            })
+                            .await,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// List the API requests [`start()`](Self::start) would send, in order,
+    /// without contacting Firecracker.
+    ///
+    /// Runs the same local validation as `start()`, so a plan is only
+    /// returned for a configuration that `start()` would send.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn example(builder: fc_sdk::VmBuilder) -> fc_sdk::Result<()> {
+    /// for request in builder.plan()? {
+    ///     println!("{} {} {}", request.method, request.path, request.body);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The [instance id check](Self::expected_id) and the version check for
+    /// optional [`Feature`]s that `start()` performs first are not part of
+    /// the plan.
+    pub fn plan(&self) -> Result<Vec<PlannedRequest>> {
+        self.validate()?;
         let boot_source = self
             .boot_source
+            .as_ref()
             .ok_or(Error::MissingConfig("boot_source"))?;
         let machine_config = self
             .machine_config
+            .as_ref()
             .ok_or(Error::MissingConfig("machine_config"))?;
 
-        // Apply logger first (if configured) — must be done before other config
-        if let Some(logger) = self.logger {
-            self.client.put_logger().body(logger).send().await?;
+        // Mirrors the request sequence of `configure_with()` and `start()`.
+        let mut plan = Vec::new();
+        if let Some(logger) = &self.logger {
+            plan.push(PlannedRequest::put("/logger", logger)?);
+        }
+        if let Some(metrics) = &self.metrics {
+            plan.push(PlannedRequest::put("/metrics", metrics)?);
         }
+        for stage in &self.apply_order {
+            match stage {
+                ConfigStage::BootSource => {
+                    plan.push(PlannedRequest::put("/boot-source", boot_source)?);
+                }
+                ConfigStage::MachineConfig => {
+                    plan.push(PlannedRequest::put("/machine-config", machine_config)?);
+                }
+                ConfigStage::CpuConfig => {
+                    if let Some(cpu_config) = &self.cpu_config {
+                        plan.push(PlannedRequest::put("/cpu-config", cpu_config)?);
+                    }
+                }
+                ConfigStage::Drives => {
+                    for drive in &self.drives {
+                        let path = format!("/drives/{}", drive.drive_id);
+                        plan.push(PlannedRequest::put(path, drive)?);
+                    }
+                }
+                ConfigStage::Pmem => {
+                    for pmem in &self.pmem_devices {
+                        plan.push(PlannedRequest::put(format!("/pmem/{}", pmem.id), pmem)?);
+                    }
+                }
+                ConfigStage::Network => {
+                    for iface in &self.network_interfaces {
+                        let path = format!("/network-interfaces/{}", iface.iface_id);
+                        plan.push(PlannedRequest::put(path, iface)?);
+                    }
+                }
+                ConfigStage::Balloon => {
+                    if let Some(balloon) = &self.balloon {
+                        plan.push(PlannedRequest::put("/balloon", balloon)?);
+                    }
+                }
+                ConfigStage::Vsock => {
+                    if let Some(vsock) = &self.vsock {
+                        plan.push(PlannedRequest::put("/vsock", vsock)?);
+                    }
+                }
+                ConfigStage::Entropy => {
+                    if let Some(entropy) = &self.entropy {
+                        plan.push(PlannedRequest::put("/entropy", entropy)?);
+                    }
+                }
+                ConfigStage::Serial => {
+                    if let Some(serial) = &self.serial {
+                        plan.push(PlannedRequest::put("/serial", serial)?);
+                    }
+                }
+                ConfigStage::MemoryHotplug => {
+                    if let Some(memory_hotplug) = &self.memory_hotplug {
+                        plan.push(PlannedRequest::put("/hotplug/memory", memory_hotplug)?);
+                    }
+                }
+                ConfigStage::Mmds => {
+                    if let Some(mmds_config) = &self.mmds_config {
+                        plan.push(PlannedRequest::put("/mmds/config", mmds_config)?);
+                    }
+                    if let Some(mmds_data) = &self.mmds_data {
+                        plan.push(PlannedRequest::put("/mmds", mmds_data)?);
+                    }
+                }
+            }
+        }
+        plan.push(PlannedRequest::put(
+            "/actions",
+            &InstanceActionInfo {
+                action_type: InstanceActionInfoActionType::InstanceStart,
+            },
+        )?);
+        Ok(plan)
+    }
 
-        // Apply metrics (if configured) — must be done before other config
-        if let Some(metrics) = self.metrics {
-            self.client.put_metrics().body(metrics).send().await?;
-        }
-
-        // Apply boot source
-        self.client
-            .put_guest_boot_source()
-            .body(boot_source)
-            .send()
-            .await?;
-
-        // Apply machine configuration
-        self.client
-            .put_machine_configuration()
-            .body(machine_config)
-            .send()
-            .await?;
-
-        // Apply CPU configuration (if configured)
-        if let Some(cpu_config) = self.cpu_config {
-            self.client
-                .put_cpu_configuration()
-                .body(cpu_config)
-                .send()
-                .await?;
+    /// Get a reference to the underlying API client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Optional features this configuration relies on, with a description
+    /// for the error message.
+    fn required_features(&self) -> Vec<(Feature, &'static str)> {
+        let mut features = Vec::new();
+        if let Some(balloon) = &self.balloon {
+            if balloon.free_page_hinting == Some(true) {
+                features.push((Feature::BalloonHinting, "balloon free page hinting"));
+            }
+            if balloon.free_page_reporting == Some(true) {
+                features.push((Feature::BalloonHinting, "balloon free page reporting"));
+            }
+        }
+        if !self.pmem_devices.is_empty() {
+            features.push((Feature::Pmem, "pmem devices"));
+        }
+        if self.memory_hotplug.is_some() {
+            features.push((Feature::MemoryHotplug, "memory hotplug"));
         }
+        features
+    }
 
-        // Apply drives
+    /// Check cross-field consistency that Firecracker would otherwise reject
+    /// (or silently accept) partway through [`start()`](Self::start).
+    fn validate(&self) -> Result<()> {
+        // A second PUT with the same id silently replaces the first device.
+        check_unique_ids("drive_id", self.drives.iter().map(|d| d.drive_id.as_str()))?;
+        check_unique_ids(
+            "iface_id",
+            self.network_interfaces.iter().map(|i| i.iface_id.as_str()),
+        )?;
+        check_unique_ids("pmem id", self.pmem_devices.iter().map(|p| p.id.as_str()))?;
         for drive in &self.drives {
-            self.client
-                .put_guest_drive_by_id()
-                .drive_id(&drive.drive_id)
-                .body(drive.clone())
-                .send()
-                .await?;
+            check_drive(drive)?;
         }
-
-        // Apply pmem devices
-        for pmem in &self.pmem_devices {
-            self.client
-                .put_guest_pmem_by_id()
-                .id(&pmem.id)
-                .body(pmem.clone())
-                .send()
-                .await?;
+        if self.vsock_conflict {
+            return Err(Error::InvalidConfig(
+                "vsock configured more than once with different settings; \
+                 Firecracker supports a single vsock device"
+                    .to_owned(),
+            ));
         }
-
-        // Apply network interfaces
-        for iface in &self.network_interfaces {
-            self.client
-                .put_guest_network_interface_by_id()
-                .iface_id(&iface.iface_id)
-                .body(iface.clone())
-                .send()
-                .await?;
+        if !self.root_with_initrd
+            && let Some(initrd) = self
+                .boot_source
+                .as_ref()
+                .and_then(|b| b.initrd_path.as_deref())
+        {
+            let root = self
+                .drives
+                .iter()
+                .find(|d| d.is_root_device)
+                .map(|d| format!("drive {}", d.drive_id))
+                .or_else(|| {
+                    self.pmem_devices
+                        .iter()
+                        .find(|p| p.root_device == Some(true))
+                        .map(|p| format!("pmem {}", p.id))
+                });
+            if let Some(root) = root {
+                return Err(Error::InvalidConfig(format!(
+                    "{root} is a root device but the boot source also sets initrd {initrd}; \
+                     use one boot mode, or call allow_root_drive_with_initrd()"
+                )));
+            }
+        }
+        if let Some(rate_limiter) = self.entropy.as_ref().and_then(|e| e.rate_limiter.as_ref()) {
+            check_token_bucket("entropy bandwidth", rate_limiter.bandwidth.as_ref())?;
+            check_token_bucket("entropy ops", rate_limiter.ops.as_ref())?;
         }
+        if self.local_checks {
+            for pmem in &self.pmem_devices {
+                check_pmem_file(pmem)?;
+            }
+            if let Some(drive) = self
+                .drives
+                .iter()
+                .find(|drive| drive.io_engine == DriveIoEngine::Async)
+                && !host_supports_io_uring()
+            {
+                return Err(Error::InvalidConfig(format!(
+                    "drive {}: the Async (io_uring) IO engine is not supported by the host kernel",
+                    drive.drive_id
+                )));
+            }
+        }
+        for stage in ConfigStage::DEFAULT_ORDER {
+            let count = self.apply_order.iter().filter(|s| **s == stage).count();
+            if count != 1 {
+                return Err(Error::InvalidConfig(format!(
+                    "apply order lists {stage:?} {count} times, expected once"
+                )));
+            }
+        }
+        if let Some(machine_config) = &self.machine_config
+            && let Some(page_size_mib) = huge_page_size_mib(machine_config.huge_pages.as_ref())
+            && machine_config.mem_size_mib % page_size_mib != 0
+        {
+            return Err(Error::InvalidConfig(format!(
+                "mem_size_mib {} is not a multiple of the {page_size_mib} MiB huge page size",
+                machine_config.mem_size_mib
+            )));
+        }
+        if let (Some(balloon), Some(machine_config)) = (&self.balloon, &self.machine_config)
+            && balloon.amount_mib > machine_config.mem_size_mib
+        {
+            return Err(Error::InvalidConfig(format!(
+                "balloon size {} MiB exceeds guest memory of {} MiB",
+                balloon.amount_mib, machine_config.mem_size_mib
+            )));
+        }
+        if let Some(mmds_config) = &self.mmds_config {
+            for iface_id in &mmds_config.network_interfaces {
+                if !self
+                    .network_interfaces
+                    .iter()
+                    .any(|iface| &iface.iface_id == iface_id)
+                {
+                    return Err(Error::InvalidConfig(format!(
+                        "MMDS references unknown network interface: {iface_id}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
 
-        // Apply balloon (if configured)
-        if let Some(balloon) = self.balloon {
-            self.client.put_balloon().body(balloon).send().await?;
+/// A [`VmBuilder`]'s configuration without its API client.
+///
+/// Build one configuration, then stamp out many microVMs from it, overriding
+/// only the per-instance settings (socket, TAP devices, ...) on each builder.
+///
+/// # Example
+///
+/// ```no_run
+/// use fc_sdk::{VmBuilder, types::*};
+///
+/// # async fn example(template: fc_sdk::VmTemplate) -> fc_sdk::Result<()> {
+/// for i in 0..4 {
+///     template
+///         .clone()
+///         .into_builder(format!("/tmp/fc-{i}.sock"))
+///         .network_interface(NetworkInterface {
+///             iface_id: "eth0".into(),
+///             host_dev_name: format!("tap{i}"),
+///             guest_mac: None,
+///             rx_rate_limiter: None,
+///             tx_rate_limiter: None,
+///         })
+///         .start()
+///         .await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct VmTemplate {
+    boot_source: Option<BootSource>,
+    machine_config: Option<MachineConfiguration>,
+    cpu_config: Option<CpuConfig>,
+    drives: Vec<Drive>,
+    pmem_devices: Vec<Pmem>,
+    network_interfaces: Vec<NetworkInterface>,
+    balloon: Option<Balloon>,
+    vsock: Option<Vsock>,
+    entropy: Option<EntropyDevice>,
+    serial: Option<SerialDevice>,
+    memory_hotplug: Option<MemoryHotplugConfig>,
+    mmds_config: Option<MmdsConfig>,
+    mmds_data: Option<serde_json::Map<String, serde_json::Value>>,
+    logger: Option<Logger>,
+    metrics: Option<Metrics>,
+    apply_order: Vec<ConfigStage>,
+    local_checks: bool,
+    root_with_initrd: bool,
+    vsock_conflict: bool,
+    start_timeout: Option<Duration>,
+    wait_for_boot: Option<Duration>,
+}
+
+impl VmTemplate {
+    /// Create a builder with this configuration connected to `socket_path`.
+    pub fn into_builder(self, socket_path: impl AsRef<Path>) -> VmBuilder {
+        self.apply_to(VmBuilder::new(socket_path))
+    }
+
+    /// Replace `builder`'s configuration with this one, keeping its client.
+    pub fn apply_to(self, mut builder: VmBuilder) -> VmBuilder {
+        builder.boot_source = self.boot_source;
+        builder.machine_config = self.machine_config;
+        builder.cpu_config = self.cpu_config;
+        builder.drives = self.drives;
+        builder.pmem_devices = self.pmem_devices;
+        builder.network_interfaces = self.network_interfaces;
+        builder.balloon = self.balloon;
+        builder.vsock = self.vsock;
+        builder.entropy = self.entropy;
+        builder.serial = self.serial;
+        builder.memory_hotplug = self.memory_hotplug;
+        builder.mmds_config = self.mmds_config;
+        builder.mmds_data = self.mmds_data;
+        builder.logger = self.logger;
+        builder.metrics = self.metrics;
+        builder.apply_order = self.apply_order;
+        builder.local_checks = self.local_checks;
+        builder.root_with_initrd = self.root_with_initrd;
+        builder.vsock_conflict = self.vsock_conflict;
+        builder.start_timeout = self.start_timeout;
+        builder.wait_for_boot = self.wait_for_boot;
+        builder
+    }
+}
+
+/// An API request that [`VmBuilder::start()`] would send; see [`VmBuilder::plan()`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedRequest {
+    /// HTTP method, e.g. `PUT`.
+    pub method: &'static str,
+    /// Request path, e.g. `/drives/rootfs`.
+    pub path: String,
+    /// JSON request body.
+    pub body: serde_json::Value,
+}
+
+impl PlannedRequest {
+    fn put(path: impl Into<String>, body: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            method: "PUT",
+            path: path.into(),
+            body: serde_json::to_value(body)?,
+        })
+    }
+}
+
+/// Builder for a [`Balloon`] device with sane defaults.
+///
+/// `deflate_on_oom` defaults to `true` and statistics are disabled until
+/// [`stats_interval()`](Self::stats_interval) is called.
+///
+/// # Example
+///
+/// ```
+/// use std::num::NonZeroU64;
+/// use fc_sdk::BalloonBuilder;
+///
+/// let balloon = BalloonBuilder::new(256)
+///     .stats_interval(NonZeroU64::new(1).unwrap())
+///     .build();
+/// assert_eq!(balloon.stats_polling_interval_s, Some(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BalloonBuilder {
+    amount_mib: i64,
+    deflate_on_oom: bool,
+    stats_polling_interval_s: Option<NonZeroU64>,
+    free_page_hinting: Option<bool>,
+    free_page_reporting: Option<bool>,
+}
+
+impl BalloonBuilder {
+    /// Create a balloon with an initial target size of `amount_mib`.
+    pub fn new(amount_mib: i64) -> Self {
+        Self {
+            amount_mib,
+            deflate_on_oom: true,
+            stats_polling_interval_s: None,
+            free_page_hinting: None,
+            free_page_reporting: None,
         }
+    }
+
+    /// Whether the balloon deflates when the guest is under memory pressure.
+    pub fn deflate_on_oom(mut self, deflate_on_oom: bool) -> Self {
+        self.deflate_on_oom = deflate_on_oom;
+        self
+    }
+
+    /// Enable balloon statistics, refreshed every `secs` seconds.
+    ///
+    /// Firecracker treats an interval of zero as "statistics disabled", so
+    /// the interval is non-zero by construction.
+    pub fn stats_interval(mut self, secs: NonZeroU64) -> Self {
+        self.stats_polling_interval_s = Some(secs);
+        self
+    }
+
+    /// Enable or disable free page hinting.
+    ///
+    /// Requires Firecracker 1.14.0 or newer ([`Feature::BalloonHinting`]);
+    /// [`VmBuilder::start()`] fails early on older releases.
+    pub fn free_page_hinting(mut self, enabled: bool) -> Self {
+        self.free_page_hinting = Some(enabled);
+        self
+    }
 
-        // Apply vsock (if configured)
-        if let Some(vsock) = self.vsock {
-            self.client.put_guest_vsock().body(vsock).send().await?;
+    /// Enable or disable free page reporting.
+    ///
+    /// The guest reports freed pages so the host can reclaim them without
+    /// inflating the balloon, which greatly helps memory overcommit. Requires
+    /// Firecracker 1.14.0 or newer ([`Feature::BalloonHinting`]);
+    /// [`VmBuilder::start()`] fails early on older releases.
+    pub fn free_page_reporting(mut self, enabled: bool) -> Self {
+        self.free_page_reporting = Some(enabled);
+        self
+    }
+
+    /// Build the [`Balloon`] device configuration.
+    pub fn build(self) -> Balloon {
+        Balloon {
+            amount_mib: self.amount_mib,
+            deflate_on_oom: self.deflate_on_oom,
+            stats_polling_interval_s: self
+                .stats_polling_interval_s
+                .map(|secs| i64::try_from(secs.get()).unwrap_or(i64::MAX)),
+            free_page_hinting: self.free_page_hinting,
+            free_page_reporting: self.free_page_reporting,
         }
+    }
+}
 
-        // Apply entropy device (if configured)
-        if let Some(entropy) = self.entropy {
-            self.client
-                .put_entropy_device()
-                .body(entropy)
-                .send()
-                .await?;
+/// Reject drive options that do not apply to the drive's backend.
+///
+/// A drive is either virtio-block, backed by `path_on_host`, or vhost-user,
+/// backed by a `socket`; Firecracker rejects options of the other kind.
+/// Every `cache_type` and `io_engine` pair is valid for virtio-block drives.
+fn check_drive(drive: &Drive) -> Result<()> {
+    let id = &drive.drive_id;
+    match (&drive.path_on_host, &drive.socket) {
+        (Some(_), Some(_)) => Err(Error::InvalidConfig(format!(
+            "drive {id}: path_on_host and socket are mutually exclusive"
+        ))),
+        (None, None) => Err(Error::InvalidConfig(format!(
+            "drive {id}: one of path_on_host or socket is required"
+        ))),
+        (None, Some(_)) if drive.is_read_only.is_some() => Err(Error::InvalidConfig(format!(
+            "drive {id}: is_read_only does not apply to vhost-user drives"
+        ))),
+        (None, Some(_)) if drive.io_engine == DriveIoEngine::Async => {
+            Err(Error::InvalidConfig(format!(
+                "drive {id}: io_engine Async does not apply to vhost-user drives; \
+                 IO is handled by the backend"
+            )))
         }
+        _ => Ok(()),
+    }
+}
+
+fn same_vsock(a: &Vsock, b: &Vsock) -> bool {
+    a.guest_cid == b.guest_cid && a.uds_path == b.uds_path && a.vsock_id == b.vsock_id
+}
 
-        // Apply serial device (if configured)
-        if let Some(serial) = self.serial {
-            self.client.put_serial_device().body(serial).send().await?;
+/// Reject a configuration that needs features newer than `version`.
+fn check_features(features: &[(Feature, &str)], version: (u64, u64, u64)) -> Result<()> {
+    for &(feature, what) in features {
+        if !feature.supported_by(version) {
+            let (major, minor, patch) = feature.min_version();
+            let (found_major, found_minor, found_patch) = version;
+            return Err(Error::InvalidConfig(format!(
+                "{what} requires Firecracker {major}.{minor}.{patch} or newer, \
+                 found {found_major}.{found_minor}.{found_patch}"
+            )));
         }
+    }
+    Ok(())
+}
 
-        // Apply memory hotplug (if configured)
-        if let Some(memory_hotplug) = self.memory_hotplug {
-            self.client
-                .put_memory_hotplug()
-                .body(memory_hotplug)
-                .send()
-                .await?;
+/// Reject a token bucket that Firecracker would silently treat as unlimited.
+fn check_token_bucket(kind: &str, bucket: Option<&TokenBucket>) -> Result<()> {
+    if let Some(bucket) = bucket
+        && (bucket.size <= 0 || bucket.refill_time <= 0)
+    {
+        return Err(Error::InvalidConfig(format!(
+            "{kind} rate limiter needs a positive size and refill_time, got {} and {}",
+            bucket.size, bucket.refill_time
+        )));
+    }
+    Ok(())
+}
+
+fn check_unique_ids<'a>(kind: &str, ids: impl Iterator<Item = &'a str>) -> Result<()> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(Error::InvalidConfig(format!("duplicate {kind}: {id}")));
         }
+    }
+    Ok(())
+}
 
-        // Apply MMDS config (if configured)
-        if let Some(mmds_config) = self.mmds_config {
-            self.client
-                .put_mmds_config()
-                .body(mmds_config)
-                .send()
-                .await?;
+/// Size of the configured huge pages in MiB, if huge pages are enabled.
+///
+/// The API only exposes 2 MiB pages; 1 GiB pages would be added here.
+fn huge_page_size_mib(huge_pages: Option<&MachineConfigurationHugePages>) -> Option<i64> {
+    match huge_pages? {
+        MachineConfigurationHugePages::None => None,
+        MachineConfigurationHugePages::X2m => Some(2),
+    }
+}
+
+/// Check that a pmem backing file exists and is a non-empty multiple of 2 MiB.
+fn check_pmem_file(pmem: &Pmem) -> Result<()> {
+    let len = std::fs::metadata(&pmem.path_on_host)
+        .map_err(|e| {
+            Error::InvalidConfig(format!(
+                "pmem {}: cannot access {}: {e}",
+                pmem.id, pmem.path_on_host
+            ))
+        })?
+        .len();
+    if len == 0 || len % PMEM_ALIGNMENT != 0 {
+        return Err(Error::InvalidConfig(format!(
+            "pmem {}: size of {} ({len} bytes) is not a non-zero multiple of 2 MiB",
+            pmem.id, pmem.path_on_host
+        )));
+    }
+    Ok(())
+}
+
+/// Pick the best drive IO engine supported by the host kernel.
+///
+/// Returns [`DriveIoEngine::Async`] (io_uring) on Linux 5.10.51+ unless
+/// io_uring is disabled via the `kernel.io_uring_disabled` sysctl, and
+/// [`DriveIoEngine::Sync`] otherwise. The version is read from the kernel
+/// release, so a distribution kernel reporting e.g. `5.10.0-21-amd64` counts
+/// as 5.10.0 whatever fixes it carries.
+pub fn detect_io_engine() -> DriveIoEngine {
+    if host_supports_io_uring() {
+        DriveIoEngine::Async
+    } else {
+        DriveIoEngine::Sync
+    }
+}
+
+fn host_supports_io_uring() -> bool {
+    // Linux 6.6+: 2 disables io_uring for all processes.
+    if std::fs::read_to_string("/proc/sys/kernel/io_uring_disabled")
+        .is_ok_and(|value| value.trim() == "2")
+    {
+        return false;
+    }
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .and_then(|release| parse_kernel_release(&release))
+        .is_some_and(|version| version >= IO_URING_MIN_KERNEL)
+}
+
+/// Parse `(major, minor, patch)` from a kernel release such as
+/// `5.10.0-21-amd64`. A missing patch level (e.g. `6.1-rc1`) counts as 0.
+fn parse_kernel_release(release: &str) -> Option<(u64, u64, u64)> {
+    let version = release
+        .trim()
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?;
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    Some((major, minor, patch))
+}
+
+/// A group of configuration requests sent by [`VmBuilder::configure()`].
+///
+/// See [`VmBuilder::apply_order()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigStage {
+    /// `PUT /boot-source`
+    BootSource,
+    /// `PUT /machine-config`
+    MachineConfig,
+    /// `PUT /cpu-config`
+    CpuConfig,
+    /// `PUT /drives/{id}` for every drive
+    Drives,
+    /// `PUT /pmem/{id}` for every pmem device
+    Pmem,
+    /// `PUT /network-interfaces/{id}` for every interface
+    Network,
+    /// `PUT /balloon`
+    Balloon,
+    /// `PUT /vsock`
+    Vsock,
+    /// `PUT /entropy`
+    Entropy,
+    /// `PUT /serial`
+    Serial,
+    /// `PUT /hotplug/memory`
+    MemoryHotplug,
+    /// `PUT /mmds/config`, then `PUT /mmds`
+    Mmds,
+}
+
+impl ConfigStage {
+    /// The order used unless overridden with [`VmBuilder::apply_order()`].
+    pub const DEFAULT_ORDER: [Self; 12] = [
+        Self::BootSource,
+        Self::MachineConfig,
+        Self::CpuConfig,
+        Self::Drives,
+        Self::Pmem,
+        Self::Network,
+        Self::Balloon,
+        Self::Vsock,
+        Self::Entropy,
+        Self::Serial,
+        Self::MemoryHotplug,
+        Self::Mmds,
+    ];
+}
+
+/// Retry policy for API requests that fail to reach Firecracker.
+///
+/// Used for the configuration requests sent by
+/// [`VmBuilder::start_with_retry()`] and for the requests of a [`Vm`] handle
+/// set up with [`Vm::with_retry()`].
+///
+/// The delay before retry `n` is `base_backoff * 2^(n - 1)`.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts per request, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Send every request exactly once.
+    pub(crate) const NONE: Self = Self::new(1, Duration::ZERO);
+
+    /// Create a policy with `max_attempts` attempts per request.
+    pub const fn new(max_attempts: u32, base_backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_backoff,
         }
+    }
 
-        // Apply MMDS data (if configured)
-        if let Some(mmds_data) = self.mmds_data {
-            self.client.put_mmds().body(mmds_data).send().await?;
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+
+    /// Run `request` until it succeeds, fails with a non-retryable error, or
+    /// the attempts are exhausted.
+    pub(crate) async fn run<T, E, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, fc_api::Error<E>>>,
+        Error: From<fc_api::Error<E>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match request().await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    let err = Error::from(err);
+                    if attempt >= self.max_attempts || !err.is_retryable() {
+                        return Err(err);
+                    }
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+            }
         }
+    }
+}
 
-        // Start the instance
-        self.client
-            .create_sync_action()
-            .body_map(|b| b.action_type(fc_api::types::InstanceActionInfoActionType::InstanceStart))
-            .send()
-            .await?;
+impl Default for RetryPolicy {
+    /// Five attempts, starting with a 50ms backoff.
+    fn default() -> Self {
+        Self::new(5, Duration::from_millis(50))
+    }
+}
 
-        Ok(Vm::new(self.client))
+/// Pass `result` through, or record it in `results` and carry on.
+fn report<T>(
+    results: &mut Option<&mut Vec<(ConfigStage, Result<()>)>>,
+    stage: ConfigStage,
+    result: Result<T>,
+) -> Result<Option<T>> {
+    match results {
+        Some(results) => match result {
+            Ok(value) => {
+                results.push((stage, Ok(())));
+                Ok(Some(value))
+            }
+            Err(err) => {
+                results.push((stage, Err(err)));
+                Ok(None)
+            }
+        },
+        None => result.map(Some),
     }
+}
 
-    /// Get a reference to the underlying API client.
-    pub fn client(&self) -> &Client {
-        &self.client
+fn mmds_config(version: MmdsConfigVersion, interface_ids: Vec<String>) -> MmdsConfig {
+    MmdsConfig {
+        version,
+        network_interfaces: interface_ids,
+        ipv4_address: DEFAULT_MMDS_IPV4_ADDRESS.to_owned(),
+        imds_compat: false,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::num::NonZeroU64;
-
     use super::*;
+    use crate::test_util::TempDir;
+    use fc_api::types::LoggerLevel;
+
+    fn boot_source() -> BootSource {
+        BootSource {
+            kernel_image_path: "/vmlinux".into(),
+            boot_args: None,
+            initrd_path: None,
+        }
+    }
+
+    /// A plain, writable file-backed drive at `/{id}.ext4`.
+    fn drive(id: &str) -> Drive {
+        Drive {
+            drive_id: id.into(),
+            path_on_host: Some(format!("/{id}.ext4")),
+            is_root_device: false,
+            is_read_only: None,
+            cache_type: Default::default(),
+            io_engine: DriveIoEngine::Sync,
+            partuuid: None,
+            rate_limiter: None,
+            socket: None,
+        }
+    }
 
     #[test]
     fn test_from_config_maps_all_fields() {
@@ -495,4 +1962,638 @@ mod tests {
         assert!(builder.logger.is_none());
         assert!(builder.metrics.is_none());
     }
+
+    #[test]
+    fn test_from_json_file_reads_config_file_format() {
+        let dir = TempDir::new("config");
+        let path = dir.join("config.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "boot-source": { "kernel_image_path": "/path/to/kernel" },
+                "machine-config": { "vcpu_count": 2, "mem_size_mib": 256 },
+                "drives": [{
+                    "drive_id": "rootfs",
+                    "path_on_host": "/path/to/rootfs.ext4",
+                    "is_root_device": true
+                }],
+                "network-interfaces": [{ "iface_id": "eth0", "host_dev_name": "tap0" }]
+            }"#,
+        )
+        .unwrap();
+
+        let builder = VmBuilder::from_json_file("/tmp/test.sock", &path).unwrap();
+
+        assert_eq!(
+            builder.boot_source.as_ref().unwrap().kernel_image_path,
+            "/path/to/kernel"
+        );
+        assert_eq!(builder.machine_config.as_ref().unwrap().mem_size_mib, 256);
+        assert_eq!(builder.drives[0].drive_id, "rootfs");
+        assert_eq!(builder.network_interfaces[0].host_dev_name, "tap0");
+    }
+
+    #[test]
+    fn test_cpu_config_from_file() {
+        let dir = TempDir::new("cpu-template");
+        let path = dir.join("cpu-template.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "kvm_capabilities": ["!56"],
+                "msr_modifiers": [{ "addr": "0x10a", "bitmap": "0b0000000000000000000000000000000000000000000000000000000000000000" }]
+            }"#,
+        )
+        .unwrap();
+        let builder = VmBuilder::new("/tmp/test.sock").cpu_config_from_file(&path);
+        let cpu_config = builder.unwrap().cpu_config.unwrap();
+        assert_eq!(cpu_config.kvm_capabilities, ["!56"]);
+        assert_eq!(cpu_config.msr_modifiers[0].addr, "0x10a");
+
+        std::fs::write(&path, "{ not json").unwrap();
+        let result = VmBuilder::new("/tmp/test.sock").cpu_config_from_file(&path);
+        assert!(matches!(result, Err(Error::Serde(_))));
+    }
+
+    #[test]
+    fn test_balloon_must_fit_in_guest_memory() {
+        let machine_config = MachineConfiguration {
+            vcpu_count: NonZeroU64::new(1).unwrap(),
+            mem_size_mib: 256,
+            smt: false,
+            track_dirty_pages: false,
+            cpu_template: None,
+            huge_pages: None,
+        };
+
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .machine_config(machine_config.clone())
+            .balloon_with(BalloonBuilder::new(128));
+        assert!(builder.validate().is_ok());
+        assert!(builder.balloon.as_ref().unwrap().deflate_on_oom);
+        assert_eq!(
+            builder.balloon.as_ref().unwrap().stats_polling_interval_s,
+            None
+        );
+
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .machine_config(machine_config)
+            .balloon_with(BalloonBuilder::new(512));
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_retry_backoff_doubles() {
+        let policy = RetryPolicy::new(4, Duration::from_millis(10));
+        assert_eq!(policy.backoff(1), Duration::from_millis(10));
+        assert_eq!(policy.backoff(2), Duration::from_millis(20));
+        assert_eq!(policy.backoff(3), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_apply_order_must_list_every_stage() {
+        let mut order = ConfigStage::DEFAULT_ORDER.to_vec();
+        order.retain(|stage| *stage != ConfigStage::Mmds);
+        order.insert(0, ConfigStage::Mmds);
+        let builder = VmBuilder::new("/tmp/test.sock").apply_order(order.clone());
+        assert!(builder.validate().is_ok());
+
+        order.pop();
+        let builder = VmBuilder::new("/tmp/test.sock").apply_order(order);
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_pmem_files_must_be_aligned() {
+        let dir = TempDir::new("pmem");
+        let path = dir.join("pmem.img");
+        let file = std::fs::File::create(&path).unwrap();
+
+        file.set_len(PMEM_ALIGNMENT).unwrap();
+        let builder = VmBuilder::new("/tmp/test.sock").pmem_from_file("pmem0", &path, true);
+        assert!(builder.validate().is_ok());
+
+        file.set_len(PMEM_ALIGNMENT + 4096).unwrap();
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+        assert!(builder.skip_local_checks().validate().is_ok());
+        std::fs::remove_file(&path).unwrap();
+
+        let builder = VmBuilder::new("/tmp/test.sock").pmem_from_file("pmem0", &path, true);
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_mmds_interfaces_must_exist() {
+        let iface = NetworkInterface {
+            iface_id: "eth0".into(),
+            guest_mac: None,
+            host_dev_name: "tap0".into(),
+            rx_rate_limiter: None,
+            tx_rate_limiter: None,
+        };
+
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .network_interface(iface.clone())
+            .mmds_v2(vec!["eth0".into()]);
+        assert!(builder.validate().is_ok());
+        let mmds_config = builder.mmds_config.as_ref().unwrap();
+        assert_eq!(mmds_config.version, MmdsConfigVersion::V2);
+        assert_eq!(mmds_config.ipv4_address, "169.254.169.254");
+
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .network_interface(iface)
+            .mmds_v1(vec!["eth1".into()]);
+        match builder.validate() {
+            Err(Error::InvalidConfig(msg)) => assert!(msg.contains("eth1")),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_huge_pages_require_aligned_memory() {
+        let machine_config = |mem_size_mib| MachineConfiguration {
+            vcpu_count: NonZeroU64::new(1).unwrap(),
+            mem_size_mib,
+            smt: false,
+            track_dirty_pages: false,
+            cpu_template: None,
+            huge_pages: Some(MachineConfigurationHugePages::X2m),
+        };
+
+        let builder = VmBuilder::new("/tmp/test.sock").machine_config(machine_config(257));
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+
+        let builder = VmBuilder::new("/tmp/test.sock").machine_config(machine_config(256));
+        assert!(builder.validate().is_ok());
+    }
+
+    #[test]
+    fn test_template_round_trip() {
+        let original = VmBuilder::new("/tmp/a.sock")
+            .machine_config_simple(NonZeroU64::new(2).unwrap(), 256)
+            .skip_local_checks();
+
+        let copy = original.template().into_builder("/tmp/b.sock");
+        assert_eq!(copy.machine_config.unwrap().mem_size_mib, 256);
+        assert!(!copy.local_checks);
+
+        let copy = original.try_clone("/tmp/c.sock").unwrap();
+        assert_eq!(copy.machine_config.unwrap().vcpu_count.get(), 2);
+    }
+
+    #[test]
+    fn test_parse_kernel_release() {
+        assert_eq!(parse_kernel_release("5.10.0-21-amd64\n"), Some((5, 10, 0)));
+        assert_eq!(parse_kernel_release("6.1.55"), Some((6, 1, 55)));
+        assert_eq!(parse_kernel_release("6.1-rc1"), Some((6, 1, 0)));
+        assert_eq!(parse_kernel_release("4.19.0-rc1"), Some((4, 19, 0)));
+        assert_eq!(parse_kernel_release("unknown"), None);
+        assert!((4, 19, 0) < IO_URING_MIN_KERNEL);
+        assert!(parse_kernel_release("5.10.50").unwrap() < IO_URING_MIN_KERNEL);
+        assert!(parse_kernel_release("5.10.51").unwrap() >= IO_URING_MIN_KERNEL);
+    }
+
+    #[test]
+    fn test_plan_follows_apply_order() {
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .boot_source(boot_source())
+            .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+            .pmem(Pmem {
+                id: "pmem0".into(),
+                path_on_host: "/pmem.img".into(),
+                read_only: None,
+                root_device: None,
+            })
+            .skip_local_checks();
+
+        let plan = builder.plan().unwrap();
+        let paths: Vec<_> = plan.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["/boot-source", "/machine-config", "/pmem/pmem0", "/actions"]
+        );
+        assert!(plan.iter().all(|r| r.method == "PUT"));
+        assert_eq!(plan[1].body["mem_size_mib"], 128);
+        assert_eq!(plan[3].body["action_type"], "InstanceStart");
+
+        assert!(matches!(
+            VmBuilder::new("/tmp/test.sock").plan(),
+            Err(Error::MissingConfig("boot_source"))
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_device_ids_rejected() {
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .drive(drive("rootfs"))
+            .drive(drive("data"));
+        assert!(builder.validate().is_ok());
+
+        let builder = builder.drive(drive("rootfs"));
+        match builder.validate() {
+            Err(Error::InvalidConfig(msg)) => assert_eq!(msg, "duplicate drive_id: rootfs"),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_entropy_rate_limiter_must_be_positive() {
+        let limiter = |size| RateLimiter {
+            bandwidth: Some(TokenBucket {
+                one_time_burst: None,
+                refill_time: 1000,
+                size,
+            }),
+            ops: None,
+        };
+
+        let builder = VmBuilder::new("/tmp/test.sock").entropy_with_rate(limiter(1024));
+        assert!(builder.validate().is_ok());
+
+        let builder = VmBuilder::new("/tmp/test.sock").entropy_with_rate(limiter(0));
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_logger_and_metrics_to_file() {
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .logger_to_file("/tmp/fc.log")
+            .metrics_to_file("/tmp/fc.metrics");
+        let logger = builder.logger.as_ref().unwrap();
+        assert_eq!(logger.level, LoggerLevel::Info);
+        assert_eq!(logger.log_path.as_deref(), Some("/tmp/fc.log"));
+        assert_eq!(
+            builder.metrics.as_ref().unwrap().metrics_path,
+            "/tmp/fc.metrics"
+        );
+
+        // An explicit level survives setting the path afterwards.
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .log_level_typed(LogLevel::Debug)
+            .logger_to_file("/tmp/fc.log");
+        assert_eq!(builder.logger.unwrap().level, LoggerLevel::Debug);
+    }
+
+    #[test]
+    fn test_check_features() {
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .balloon_with(BalloonBuilder::new(64).free_page_reporting(true));
+        let features = builder.required_features();
+        assert_eq!(features.len(), 1);
+        assert!(check_features(&features, (1, 14, 0)).is_ok());
+
+        let err = check_features(&features, (1, 13, 1)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: balloon free page reporting requires Firecracker \
+             1.14.0 or newer, found 1.13.1"
+        );
+
+        // Explicitly disabling the flag needs no version check.
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .balloon_with(BalloonBuilder::new(64).free_page_reporting(false));
+        assert!(builder.required_features().is_empty());
+    }
+
+    #[test]
+    fn test_drive_backend_options() {
+        let drive = Drive {
+            is_read_only: Some(false),
+            cache_type: fc_api::types::DriveCacheType::Writeback,
+            io_engine: DriveIoEngine::Async,
+            ..drive("data")
+        };
+        assert!(check_drive(&drive).is_ok());
+
+        let vhost_user = Drive {
+            path_on_host: None,
+            is_read_only: None,
+            io_engine: DriveIoEngine::Sync,
+            socket: Some("/tmp/vhost.sock".into()),
+            ..drive.clone()
+        };
+        assert!(check_drive(&vhost_user).is_ok());
+
+        for invalid in [
+            Drive {
+                socket: Some("/tmp/vhost.sock".into()),
+                ..drive.clone()
+            },
+            Drive {
+                path_on_host: None,
+                ..drive.clone()
+            },
+            Drive {
+                is_read_only: Some(true),
+                ..vhost_user.clone()
+            },
+            Drive {
+                io_engine: DriveIoEngine::Async,
+                ..vhost_user
+            },
+        ] {
+            assert!(matches!(
+                check_drive(&invalid),
+                Err(Error::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_vsock_configured_twice() {
+        let vsock = |guest_cid| Vsock {
+            guest_cid,
+            uds_path: "/tmp/v.sock".into(),
+            vsock_id: None,
+        };
+
+        // Repeating the same device is harmless.
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .vsock(vsock(3))
+            .vsock(vsock(3));
+        assert!(builder.validate().is_ok());
+
+        let builder = builder.vsock(vsock(4));
+        assert!(matches!(builder.validate(), Err(Error::InvalidConfig(_))));
+
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .try_vsock(vsock(3))
+            .unwrap();
+        assert!(builder.try_vsock(vsock(3)).is_err());
+    }
+
+    #[test]
+    fn test_root_drive_overlay() {
+        let dir = TempDir::new("overlay");
+        let base = dir.join("base.ext4");
+        std::fs::write(&base, b"rootfs").unwrap();
+
+        let builder = VmBuilder::new("/tmp/test.sock")
+            .root_drive_overlay("root", &base, dir.join("overlay.ext4"))
+            .unwrap();
+        let [root] = builder.drives.as_slice() else {
+            panic!("expected one drive, got {:?}", builder.drives);
+        };
+        assert_eq!(root.drive_id, "root");
+        assert!(root.is_root_device);
+        assert_eq!(root.is_read_only, Some(false));
+        assert_eq!(
+            root.path_on_host.as_deref(),
+            Some(dir.join("overlay.ext4").to_str().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_serial_to_file() {
+        let builder = VmBuilder::new("/tmp/test.sock");
+        assert_eq!(builder.serial_out_path(), None);
+
+        let builder = builder.serial_to_file("/tmp/fc.serial");
+        assert_eq!(builder.serial_out_path(), Some(Path::new("/tmp/fc.serial")));
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_mmds_data_is_applied_the_same_for_both_versions() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let data = json!({ "latest": { "meta-data": { "instance-id": "i-1" } } });
+        for version in [MmdsConfigVersion::V1, MmdsConfigVersion::V2] {
+            let mock = MockClient::new().unwrap();
+            mock.vm_builder()
+                .boot_source(boot_source())
+                .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+                .network_interface(NetworkInterface {
+                    iface_id: "eth0".into(),
+                    guest_mac: None,
+                    host_dev_name: "tap0".into(),
+                    rx_rate_limiter: None,
+                    tx_rate_limiter: None,
+                })
+                .mmds_config(mmds_config(version, vec!["eth0".into()]))
+                .mmds_data(data.as_object().unwrap().clone())
+                .start()
+                .await
+                .unwrap();
+
+            let requests = mock.requests();
+            let mmds: Vec<_> = requests
+                .iter()
+                .filter(|r| r.path.starts_with("/mmds"))
+                .map(|r| (r.method.as_str(), r.path.as_str(), r.body.as_ref().unwrap()))
+                .collect();
+            assert_eq!(mmds.len(), 2);
+            assert_eq!(mmds[0].0, "PUT");
+            assert_eq!(mmds[0].1, "/mmds/config");
+            assert_eq!(mmds[0].2["version"], json!(version));
+            assert_eq!(mmds[1], ("PUT", "/mmds", &data));
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_start_matches_plan() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        let builder = mock
+            .vm_builder()
+            .boot_source(boot_source())
+            .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+            .logger_to_file("/fc.log");
+
+        let plan = builder.plan().unwrap();
+        builder.start().await.unwrap();
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), plan.len());
+        for (request, planned) in requests.iter().zip(&plan) {
+            assert_eq!(request.method, planned.method);
+            assert_eq!(request.path, planned.path);
+            assert_eq!(request.body.as_ref(), Some(&planned.body));
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_start_waits_for_boot() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let instance = |state| {
+            Some(json!({
+                "app_name": "Firecracker",
+                "id": "mock",
+                "state": state,
+                "vmm_version": "1.14.0",
+            }))
+        };
+        let builder = |mock: &MockClient| {
+            mock.vm_builder()
+                .boot_source(boot_source())
+                .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+                .wait_for_boot(Duration::from_millis(500))
+        };
+
+        let mock = MockClient::new().unwrap();
+        mock.respond("GET", "/", 200, instance("Not started"));
+        mock.respond("GET", "/", 200, instance("Running"));
+        builder(&mock).start().await.unwrap();
+        let calls = mock.calls();
+        assert_eq!(calls[calls.len() - 3..], ["PUT /actions", "GET /", "GET /"]);
+
+        let mock = MockClient::new().unwrap();
+        mock.respond("GET", "/", 200, instance("Not started"));
+        let result = builder(&mock).start().await;
+        assert!(matches!(
+            result,
+            Err(Error::Timeout("microVM to be running"))
+        ));
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_apply_all_collect() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        mock.respond_fault("PUT", "/drives/data", 400, "No such file or directory");
+        let results = mock
+            .vm_builder()
+            .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+            .drive(drive("rootfs"))
+            .drive(drive("data"))
+            .drive(drive("scratch"))
+            .apply_all_collect()
+            .await;
+
+        let summary: Vec<_> = results
+            .iter()
+            .map(|(stage, result)| (*stage, result.is_ok()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (ConfigStage::BootSource, false),
+                (ConfigStage::MachineConfig, true),
+                (ConfigStage::Drives, true),
+                (ConfigStage::Drives, false),
+                (ConfigStage::Drives, true),
+            ]
+        );
+        assert!(matches!(
+            results[0].1,
+            Err(Error::MissingConfig("boot_source"))
+        ));
+        assert_eq!(
+            results[3].1.as_ref().unwrap_err().fault_message(),
+            Some("No such file or directory")
+        );
+        assert_eq!(
+            mock.calls(),
+            [
+                "PUT /machine-config",
+                "PUT /drives/rootfs",
+                "PUT /drives/data",
+                "PUT /drives/scratch",
+            ]
+        );
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_expected_id() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        let builder = mock
+            .vm_builder()
+            .boot_source(boot_source())
+            .machine_config_simple(NonZeroU64::new(1).unwrap(), 128);
+
+        let result = builder
+            .try_clone(mock.socket_path())
+            .unwrap()
+            .expected_id("vm-2")
+            .configure()
+            .await;
+        match result {
+            Err(Error::Other(msg)) => assert!(msg.contains("\"vm-2\"")),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("connected to the wrong instance"),
+        }
+        assert_eq!(mock.calls(), ["GET /"]);
+
+        builder.expected_id("mock").configure().await.unwrap();
+        assert_eq!(mock.calls()[1..3], ["GET /", "PUT /boot-source"]);
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_start_rejects_feature_too_new_for_firecracker() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        mock.respond(
+            "GET",
+            "/version",
+            200,
+            Some(serde_json::json!({ "firecracker_version": "1.13.1" })),
+        );
+        let result = mock
+            .vm_builder()
+            .boot_source(boot_source())
+            .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+            .balloon_with(BalloonBuilder::new(64).free_page_reporting(true))
+            .start()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidConfig(_))));
+        assert_eq!(mock.calls(), ["GET /version"]);
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_start_stops_at_first_api_error() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        mock.respond_fault("PUT", "/machine-config", 400, "Invalid vCPU count.");
+        let err = mock
+            .vm_builder()
+            .boot_source(boot_source())
+            .machine_config_simple(NonZeroU64::new(1).unwrap(), 128)
+            .start()
+            .await
+            .err()
+            .unwrap();
+
+        assert_eq!(err.fault_message(), Some("Invalid vCPU count."));
+        assert_eq!(mock.calls(), ["PUT /boot-source", "PUT /machine-config"]);
+    }
+
+    #[test]
+    fn test_initrd_conflicts_with_root_drive() {
+        let root = Drive {
+            is_root_device: true,
+            ..drive("rootfs")
+        };
+        let builder = VmBuilder::new("/tmp/test.sock").boot_from_initrd(
+            "/vmlinux",
+            "/initrd.img",
+            Some("console=ttyS0".into()),
+        );
+        assert_eq!(
+            builder.boot_source.as_ref().unwrap().initrd_path.as_deref(),
+            Some("/initrd.img")
+        );
+        assert!(builder.validate().is_ok());
+
+        let builder = builder.drive(root);
+        match builder.validate() {
+            Err(Error::InvalidConfig(msg)) => assert!(msg.contains("drive rootfs")),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(builder.allow_root_drive_with_initrd().validate().is_ok());
+    }
 }
@@ -1,14 +1,280 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 
 use fc_api::Client;
 
+use crate::builder::RetryPolicy;
+use crate::error::{Error, Result};
+use crate::trace::traced;
+use crate::vm::Vm;
+
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::{MockClient, RecordedRequest};
+
 /// Creates a `fc_api::Client` connected via Unix socket.
+///
+/// # Panics
+///
+/// Panics if the HTTP client cannot be constructed; see [`try_connect`].
 pub fn connect(socket_path: impl AsRef<Path>) -> Client {
+    try_connect(socket_path).expect("failed to build reqwest client with unix socket")
+}
+
+/// Creates a `fc_api::Client` connected via Unix socket, returning an error
+/// instead of panicking if the HTTP client cannot be constructed.
+pub fn try_connect(socket_path: impl AsRef<Path>) -> Result<Client> {
     let socket_path = socket_path.as_ref();
     let client = reqwest::Client::builder()
         .unix_socket(socket_path)
-        .build()
-        .expect("failed to build reqwest client with unix socket");
+        .build()?;
     // The base URL host is ignored for Unix sockets; we use "http://localhost".
-    Client::new_with_client("http://localhost", client)
+    Ok(Client::new_with_client("http://localhost", client))
+}
+
+/// Creates a client like [`try_connect`] whose requests fail if no complete
+/// response arrives within `timeout`.
+///
+/// The limit applies to every request made through the client, so keep it
+/// above the slowest expected operation (e.g. snapshot creation on a large
+/// guest). A request that times out fails with a communication error.
+pub fn connect_with_timeout(socket_path: impl AsRef<Path>, timeout: Duration) -> Result<Client> {
+    let socket_path = socket_path.as_ref();
+    let client = reqwest::Client::builder()
+        .unix_socket(socket_path)
+        .timeout(timeout)
+        .build()?;
+    Ok(Client::new_with_client("http://localhost", client))
+}
+
+/// Options for [`connect_with_options`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnectOptions {
+    timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+}
+
+impl ConnectOptions {
+    /// No timeout and no retries, like [`try_connect`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail requests without a complete response within `timeout`; see
+    /// [`connect_with_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retry idempotent requests that fail to reach the API; see
+    /// [`Vm::with_retry()`].
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+}
+
+/// Creates a [`Vm`] handle for the socket with `options` applied.
+///
+/// Returns a `Vm` rather than a [`Client`] because retries are performed by
+/// the `Vm` methods; the generated client has no hook for them. No request
+/// is sent.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use fc_sdk::RetryPolicy;
+/// use fc_sdk::connection::{ConnectOptions, connect_with_options};
+///
+/// # async fn example() -> fc_sdk::Result<()> {
+/// let options = ConnectOptions::new().retry(RetryPolicy::new(3, Duration::from_millis(20)));
+/// let vm = connect_with_options("/tmp/firecracker.sock", options)?;
+/// vm.pause().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn connect_with_options(socket_path: impl AsRef<Path>, options: ConnectOptions) -> Result<Vm> {
+    let client = match options.timeout {
+        Some(timeout) => connect_with_timeout(socket_path, timeout)?,
+        None => try_connect(socket_path)?,
+    };
+    let vm = Vm::from_client(client);
+    Ok(match options.retry {
+        Some(policy) => vm.with_retry(policy),
+        None => vm,
+    })
+}
+
+/// Environment variable naming the Firecracker API socket, read by
+/// [`connect_from_env`].
+pub const API_SOCKET_ENV: &str = "FIRECRACKER_API_SOCK";
+
+/// Creates a client like [`try_connect`] for the socket named by the
+/// [`FIRECRACKER_API_SOCK`](API_SOCKET_ENV) environment variable.
+///
+/// Returns [`Error::MissingConfig`] if the variable is unset or empty.
+pub fn connect_from_env() -> Result<Client> {
+    connect_from_var(std::env::var_os(API_SOCKET_ENV))
+}
+
+fn connect_from_var(socket_path: Option<std::ffi::OsString>) -> Result<Client> {
+    match socket_path {
+        Some(path) if !path.is_empty() => try_connect(path),
+        _ => Err(Error::MissingConfig(API_SOCKET_ENV)),
+    }
+}
+
+/// Creates a client like [`connect`] and confirms the socket is serving the API.
+///
+/// Sends a `GET /version` request and returns the client only if it succeeds.
+/// Useful when adopting a socket created by another process, where it cannot
+/// be assumed that Firecracker is ready.
+pub async fn connect_and_ping(socket_path: impl AsRef<Path>) -> Result<Client> {
+    let client = try_connect(socket_path)?;
+    traced(
+        "get_firecracker_version",
+        client.get_firecracker_version().send(),
+    )
+    .await?;
+    Ok(client)
+}
+
+/// Hands out clients for Unix sockets, creating at most one per socket path.
+///
+/// Cloned clients share the underlying connection pool, so repeated calls
+/// against the same socket avoid building a new HTTP client each time.
+///
+/// # Example
+///
+/// ```no_run
+/// use fc_sdk::{Vm, connection::SocketClientCache};
+///
+/// # async fn example(cache: &SocketClientCache) -> fc_sdk::Result<()> {
+/// let vm = Vm::from_client(cache.get("/tmp/firecracker.sock")?);
+/// vm.pause().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SocketClientCache {
+    clients: Mutex<HashMap<PathBuf, Client>>,
+}
+
+impl SocketClientCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the client for `socket_path`, creating it like [`try_connect`] on
+    /// first use.
+    pub fn get(&self, socket_path: impl AsRef<Path>) -> Result<Client> {
+        let socket_path = socket_path.as_ref();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(client) = clients.get(socket_path) {
+            return Ok(client.clone());
+        }
+        let client = try_connect(socket_path)?;
+        clients.insert(socket_path.to_owned(), client.clone());
+        Ok(client)
+    }
+
+    /// Drop the cached client for `socket_path`, e.g. after the microVM exits.
+    pub fn remove(&self, socket_path: impl AsRef<Path>) {
+        self.clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(socket_path.as_ref());
+    }
+
+    /// Number of cached clients.
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Whether the cache holds no clients.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serve `socket_path`, resetting the first connection and answering
+    /// every later request with `response`. Returns the connection count.
+    fn flaky_server(socket_path: &Path, response: String) -> Arc<AtomicUsize> {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let listener = tokio::net::UnixListener::bind(socket_path).unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let count = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                if count.fetch_add(1, Ordering::SeqCst) == 0 {
+                    // Closing with the request still unread resets the connection.
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    continue;
+                }
+                let mut stream = BufReader::new(stream);
+                let mut line = String::new();
+                while stream.read_line(&mut line).await.unwrap() > 2 {
+                    line.clear();
+                }
+                stream
+                    .get_mut()
+                    .write_all(response.as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+        connections
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_options_retries_idempotent_requests() {
+        let socket_path = crate::process::unique_socket_path();
+        let body =
+            r#"{"app_name":"Firecracker","id":"vm","state":"Running","vmm_version":"1.14.0"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let connections = flaky_server(&socket_path, response);
+        let options = ConnectOptions::new().retry(RetryPolicy::new(3, Duration::from_millis(10)));
+        let vm = connect_with_options(&socket_path, options).unwrap();
+        assert_eq!(vm.describe().await.unwrap().id, "vm");
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_with_options_sends_actions_once() {
+        let socket_path = crate::process::unique_socket_path();
+        let connections = flaky_server(&socket_path, "HTTP/1.1 204 No Content\r\n\r\n".to_owned());
+        let options = ConnectOptions::new().retry(RetryPolicy::new(3, Duration::from_millis(10)));
+        let vm = connect_with_options(&socket_path, options).unwrap();
+        let err = vm.start().await.unwrap_err();
+        assert!(err.is_retryable());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+
+    #[test]
+    fn test_connect_from_env_requires_socket() {
+        assert!(matches!(
+            connect_from_var(None),
+            Err(Error::MissingConfig(API_SOCKET_ENV))
+        ));
+        assert!(connect_from_var(Some("".into())).is_err());
+        assert!(connect_from_var(Some("/tmp/firecracker.sock".into())).is_ok());
+    }
 }
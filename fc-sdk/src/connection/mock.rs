@@ -0,0 +1,293 @@
+//! In-process stand-in for the Firecracker API, for tests.
+//!
+//! [`MockClient`] serves HTTP on a temporary Unix socket, so the [`Client`]
+//! it hands out is a real one and drives [`VmBuilder`] and [`Vm`] unchanged.
+//! Every request is recorded; responses are canned per method and path.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use fc_api::Client;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+
+use crate::builder::VmBuilder;
+use crate::error::Result;
+use crate::vm::Vm;
+
+/// A request received by a [`MockClient`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    /// HTTP method, e.g. `"PUT"`.
+    pub method: String,
+    /// Request path, e.g. `"/boot-source"`.
+    pub path: String,
+    /// JSON body, if the request had one.
+    pub body: Option<Value>,
+}
+
+/// A canned response.
+#[derive(Debug, Clone)]
+struct Response {
+    status: u16,
+    body: Option<Value>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    requests: Vec<RecordedRequest>,
+    responses: HashMap<(String, String), VecDeque<Response>>,
+}
+
+impl State {
+    /// Pop the next canned response for a route, keeping the last one so it
+    /// repeats.
+    fn response(&mut self, method: &str, path: &str) -> Response {
+        let key = (method.to_owned(), path.to_owned());
+        match self.responses.get_mut(&key) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().unwrap(),
+            Some(queue) if !queue.is_empty() => queue[0].clone(),
+            _ => default_response(method, path),
+        }
+    }
+}
+
+/// A fake Firecracker API server that records requests.
+///
+/// Without canned responses, `GET /` reports a running instance, `GET
+/// /version` and `GET /vm/config` return minimal bodies, other `GET`s fail
+/// with 400, and every other request succeeds with 204.
+///
+/// Must be created inside a Tokio runtime. The socket is removed on drop.
+///
+/// # Example
+///
+/// ```no_run
+/// use fc_sdk::connection::MockClient;
+///
+/// # async fn example() -> fc_sdk::Result<()> {
+/// let mock = MockClient::new()?;
+/// mock.vm().pause().await?;
+/// assert_eq!(mock.requests()[0].path, "/vm");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MockClient {
+    socket_path: PathBuf,
+    state: Arc<Mutex<State>>,
+    server: JoinHandle<()>,
+}
+
+impl MockClient {
+    /// Start serving on a fresh socket in the temporary directory.
+    pub fn new() -> Result<Self> {
+        let socket_path = crate::process::unique_socket_path();
+        let listener = UnixListener::bind(&socket_path)?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let server = tokio::spawn(serve(listener, Arc::clone(&state)));
+        Ok(Self {
+            socket_path,
+            state,
+            server,
+        })
+    }
+
+    /// Path of the socket being served.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// A client connected to this mock.
+    pub fn client(&self) -> Client {
+        crate::connection::connect(&self.socket_path)
+    }
+
+    /// A [`Vm`] handle backed by this mock.
+    pub fn vm(&self) -> Vm {
+        Vm::from_client(self.client())
+    }
+
+    /// A [`VmBuilder`] backed by this mock.
+    pub fn vm_builder(&self) -> VmBuilder {
+        VmBuilder::with_client(self.client())
+    }
+
+    /// Queue a response for `method` and `path`.
+    ///
+    /// Queued responses are returned in order; the last one repeats. A
+    /// `None` body is sent as an empty response.
+    pub fn respond(&self, method: &str, path: &str, status: u16, body: Option<Value>) {
+        self.lock()
+            .responses
+            .entry((method.to_owned(), path.to_owned()))
+            .or_default()
+            .push_back(Response { status, body });
+    }
+
+    /// Queue an API error carrying `fault_message`.
+    pub fn respond_fault(&self, method: &str, path: &str, status: u16, fault_message: &str) {
+        self.respond(
+            method,
+            path,
+            status,
+            Some(json!({ "fault_message": fault_message })),
+        );
+    }
+
+    /// All requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// `"{method} {path}"` for each request received so far.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock()
+            .requests
+            .iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockClient {
+    fn drop(&mut self) {
+        self.server.abort();
+        std::fs::remove_file(&self.socket_path).ok();
+    }
+}
+
+fn default_response(method: &str, path: &str) -> Response {
+    let ok = |body| Response {
+        status: 200,
+        body: Some(body),
+    };
+    match (method, path) {
+        ("GET", "/") => ok(json!({
+            "app_name": "Firecracker",
+            "id": "mock",
+            "state": "Running",
+            "vmm_version": "1.14.0",
+        })),
+        ("GET", "/version") => ok(json!({ "firecracker_version": "1.14.0" })),
+        ("GET", "/vm/config") => ok(json!({})),
+        ("GET", _) => Response {
+            status: 400,
+            body: Some(json!({ "fault_message": format!("mock: no response for GET {path}") })),
+        },
+        _ => Response {
+            status: 204,
+            body: None,
+        },
+    }
+}
+
+async fn serve(listener: UnixListener, state: Arc<Mutex<State>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(serve_connection(stream, Arc::clone(&state)));
+    }
+}
+
+/// Serve keep-alive HTTP/1.1 requests on one connection until it closes.
+async fn serve_connection(stream: UnixStream, state: Arc<Mutex<State>>) -> std::io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if stream.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let path = parts.next().unwrap_or_default().to_owned();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            stream.read_line(&mut header).await?;
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+        let mut body = vec![0; content_length];
+        stream.read_exact(&mut body).await?;
+
+        let response = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            state.requests.push(RecordedRequest {
+                method: method.clone(),
+                path: path.clone(),
+                body: serde_json::from_slice(&body).ok(),
+            });
+            state.response(&method, &path)
+        };
+
+        let body = response
+            .body
+            .map(|body| body.to_string())
+            .unwrap_or_default();
+        let head = format!(
+            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+            response.status,
+            body.len()
+        );
+        let stream = stream.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fc_api::types::InstanceInfoState;
+
+    #[tokio::test]
+    async fn test_mock_records_requests_and_replays_responses() {
+        let mock = MockClient::new().unwrap();
+        for state in ["Not started", "Paused"] {
+            let body = json!({
+                "app_name": "Firecracker",
+                "id": "mock",
+                "state": state,
+                "vmm_version": "1.14.0",
+            });
+            mock.respond("GET", "/", 200, Some(body));
+        }
+        mock.respond_fault("PATCH", "/vm", 400, "The microVM is not running.");
+
+        let vm = mock.vm();
+        assert_eq!(
+            vm.describe().await.unwrap().state,
+            InstanceInfoState::NotStarted
+        );
+        assert_eq!(
+            vm.describe().await.unwrap().state,
+            InstanceInfoState::Paused
+        );
+        assert_eq!(
+            vm.describe().await.unwrap().state,
+            InstanceInfoState::Paused
+        );
+
+        let err = vm.pause().await.unwrap_err();
+        assert_eq!(err.fault_message(), Some("The microVM is not running."));
+        assert_eq!(
+            mock.requests().last().unwrap().body,
+            Some(json!({ "state": "Paused" }))
+        );
+        assert_eq!(mock.calls(), ["GET /", "GET /", "GET /", "PATCH /vm"]);
+    }
+}
@@ -17,18 +17,39 @@ pub enum Error {
     /// I/O error.
     Io(std::io::Error),
 
+    /// JSON serialization or deserialization error.
+    Serde(serde_json::Error),
+
     /// Failed to spawn a process.
     SpawnFailed(std::io::Error),
 
     /// Timed out waiting for the API socket to become available.
-    SocketTimeout(PathBuf),
+    ///
+    /// `pid` is the process that was still running when the wait gave up, if known.
+    SocketTimeout { path: PathBuf, pid: Option<u32> },
 
     /// The process exited unexpectedly.
     ProcessExited(Option<ExitStatus>),
 
+    /// Firecracker rejected an operation that is not allowed in the microVM's
+    /// current state (e.g. a post-boot-only update sent before boot).
+    InvalidStateForOperation {
+        operation: &'static str,
+        fault_message: String,
+    },
+
+    /// Timed out waiting for an operation to complete.
+    Timeout(&'static str),
+
+    /// The operation was aborted through its cancellation token.
+    Cancelled,
+
     /// Missing required configuration.
     MissingConfig(&'static str),
 
+    /// Configuration rejected by local validation before contacting Firecracker.
+    InvalidConfig(String),
+
     /// Other error.
     Other(String),
 }
@@ -40,28 +61,127 @@ impl std::error::Error for Error {
             Self::ApiNoBody(e) => Some(e),
             Self::Http(e) => Some(e),
             Self::Io(e) => Some(e),
+            Self::Serde(e) => Some(e),
             Self::SpawnFailed(e) => Some(e),
             _ => None,
         }
     }
 }
 
+impl Error {
+    /// The `fault_message` reported by Firecracker, if this is an API error that carries one.
+    pub fn fault_message(&self) -> Option<&str> {
+        match self {
+            Self::Api(e) => match e.as_ref() {
+                fc_api::Error::ErrorResponse(response) => response.fault_message.as_deref(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the request failed at the connection level and may succeed if
+    /// retried, e.g. connection refused while Firecracker is starting up.
+    ///
+    /// A connection reset also counts. The request may then have reached the
+    /// API, so only idempotent requests should be retried on it.
+    pub fn is_retryable(&self) -> bool {
+        self.transport()
+            .is_some_and(|e| e.is_connect() || is_connection_reset(e))
+    }
+
+    /// Whether no connection to the API could be made at all, e.g. because
+    /// the socket is gone after Firecracker exited.
+    pub(crate) fn is_connect_failure(&self) -> bool {
+        self.transport().is_some_and(reqwest::Error::is_connect)
+    }
+
+    /// The underlying transport error of a request that did not get a response.
+    fn transport(&self) -> Option<&reqwest::Error> {
+        match self {
+            Self::Api(e) => match e.as_ref() {
+                fc_api::Error::CommunicationError(e) => Some(e),
+                _ => None,
+            },
+            Self::ApiNoBody(e) => match e.as_ref() {
+                fc_api::Error::CommunicationError(e) => Some(e),
+                _ => None,
+            },
+            Self::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Translate Firecracker's "operation not supported" fault into
+    /// [`Error::InvalidStateForOperation`], leaving other errors untouched.
+    pub(crate) fn for_operation(self, operation: &'static str) -> Self {
+        let fault = self
+            .fault_message()
+            .filter(|msg| msg.contains("not supported"))
+            .map(str::to_owned);
+        match fault {
+            Some(fault_message) => Self::InvalidStateForOperation {
+                operation,
+                fault_message,
+            },
+            None => self,
+        }
+    }
+}
+
+/// Whether `err` was caused by the peer resetting the connection.
+fn is_connection_reset(err: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(err);
+    while let Some(err) = source {
+        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::BrokenPipe
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Api(e) => write!(f, "API error: {e}"),
+            Self::Api(e) => match (e.status(), self.fault_message()) {
+                (Some(status), Some(msg)) => write!(f, "API error: {}: {msg}", status.as_u16()),
+                _ => write!(f, "API error: {e}"),
+            },
             Self::ApiNoBody(e) => write!(f, "API error: {e}"),
             Self::Http(e) => write!(f, "HTTP error: {e}"),
             Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Serde(e) => write!(f, "JSON error: {e}"),
             Self::SpawnFailed(e) => write!(f, "failed to spawn process: {e}"),
-            Self::SocketTimeout(path) => {
+            Self::SocketTimeout {
+                path,
+                pid: Some(pid),
+            } => write!(
+                f,
+                "timed out waiting for socket: {} (pid {pid})",
+                path.display()
+            ),
+            Self::SocketTimeout { path, pid: None } => {
                 write!(f, "timed out waiting for socket: {}", path.display())
             }
             Self::ProcessExited(Some(status)) => {
                 write!(f, "process exited unexpectedly: {status}")
             }
             Self::ProcessExited(None) => write!(f, "process exited unexpectedly"),
+            Self::InvalidStateForOperation {
+                operation,
+                fault_message,
+            } => write!(
+                f,
+                "{operation} not allowed in the current microVM state: {fault_message}"
+            ),
+            Self::Timeout(operation) => write!(f, "timed out waiting for {operation}"),
+            Self::Cancelled => write!(f, "operation cancelled"),
             Self::MissingConfig(field) => write!(f, "missing required configuration: {field}"),
+            Self::InvalidConfig(msg) => write!(f, "invalid configuration: {msg}"),
             Self::Other(msg) => write!(f, "{msg}"),
         }
     }
@@ -91,4 +211,139 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Serde(err)
+    }
+}
+
+/// Convert into an [`std::io::Error`] for callers built around `io::Result`.
+///
+/// [`Error::Io`] is unwrapped as is; other variants keep the SDK error as the
+/// inner error, with a kind matching the failure (e.g.
+/// [`Error::SocketTimeout`] becomes [`ErrorKind::TimedOut`]).
+///
+/// [`ErrorKind::TimedOut`]: std::io::ErrorKind::TimedOut
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        use std::io::ErrorKind;
+
+        if let Error::Io(e) = err {
+            return e;
+        }
+        let kind = match &err {
+            Error::SpawnFailed(e) => e.kind(),
+            Error::SocketTimeout { .. } | Error::Timeout(_) => ErrorKind::TimedOut,
+            Error::MissingConfig(_) | Error::InvalidConfig(_) => ErrorKind::InvalidInput,
+            Error::Serde(_) => ErrorKind::InvalidData,
+            _ => ErrorKind::Other,
+        };
+        Self::new(kind, err)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(fault_message: &str) -> Error {
+        let body = fc_api::types::Error {
+            fault_message: Some(fault_message.into()),
+        };
+        let response = fc_api::ResponseValue::new(
+            body,
+            reqwest::StatusCode::BAD_REQUEST,
+            reqwest::header::HeaderMap::new(),
+        );
+        fc_api::Error::ErrorResponse(response).into()
+    }
+
+    #[test]
+    fn test_not_supported_fault_maps_to_invalid_state() {
+        let err =
+            api_error("The requested operation is not supported before starting the microVM.")
+                .for_operation("update_drive");
+        match err {
+            Error::InvalidStateForOperation {
+                operation,
+                fault_message,
+            } => {
+                assert_eq!(operation, "update_drive");
+                assert!(fault_message.contains("before starting"));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_other_faults_are_left_untouched() {
+        let err = api_error("Drive not found").for_operation("update_drive");
+        assert!(matches!(err, Error::Api(_)));
+        assert_eq!(err.fault_message(), Some("Drive not found"));
+    }
+
+    #[tokio::test]
+    async fn test_connection_reset_is_retryable() {
+        let socket_path = crate::process::unique_socket_path();
+        // Closing with the request still unread resets the connection.
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            drop(stream);
+        });
+        let client = crate::connection::connect(&socket_path);
+        let err = client.get_firecracker_version().send().await.unwrap_err();
+        server.await.unwrap();
+        let err = Error::from(err);
+        std::fs::remove_file(&socket_path).ok();
+        assert!(err.is_retryable(), "{err:?}");
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        use std::io::ErrorKind;
+
+        let err = std::io::Error::from(Error::SocketTimeout {
+            path: "/tmp/fc.sock".into(),
+            pid: None,
+        });
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(
+            err.to_string(),
+            "timed out waiting for socket: /tmp/fc.sock"
+        );
+        assert!(err.get_ref().unwrap().is::<Error>());
+
+        let err = std::io::Error::from(Error::Io(ErrorKind::NotFound.into()));
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(err.get_ref().is_none());
+
+        let err = std::io::Error::from(Error::SpawnFailed(ErrorKind::PermissionDenied.into()));
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(
+            std::io::Error::from(Error::InvalidConfig("bad".into())).kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            std::io::Error::from(api_error("Drive not found")).kind(),
+            ErrorKind::Other
+        );
+        // Not `Interrupted`: I/O loops retry that kind automatically.
+        assert_eq!(
+            std::io::Error::from(Error::Cancelled).kind(),
+            ErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_api_error_display_includes_fault_message() {
+        let err = api_error("The drive ID does not exist.");
+        assert_eq!(
+            err.to_string(),
+            "API error: 400: The drive ID does not exist."
+        );
+    }
+}
@@ -66,18 +66,36 @@
 pub mod builder;
 pub mod connection;
 pub mod error;
+#[cfg(feature = "host-net")]
+pub mod net;
 pub mod process;
+pub mod restore;
+pub mod snapshot;
+#[cfg(test)]
+mod test_util;
+mod trace;
+pub mod version;
 pub mod vm;
 
-pub use builder::VmBuilder;
+pub use builder::{
+    BalloonBuilder, ConfigStage, PlannedRequest, RetryPolicy, VmBuilder, VmTemplate,
+    detect_io_engine,
+};
 pub use error::{Error, Result};
 pub use process::{
-    DetachedFirecrackerProcess, FirecrackerProcess, FirecrackerProcessBuilder, JailerProcessBuilder,
+    DetachedFirecrackerProcess, FirecrackerProcess, FirecrackerProcessBuilder, JailerLayout,
+    JailerProcessBuilder, LogLevel, RunningVm, ShutdownGuard,
 };
-pub use vm::{Vm, restore, restore_with_client};
+pub use restore::RestoreBuilder;
+pub use snapshot::SnapshotInfo;
+pub use version::Feature;
+pub use vm::{Vm, restore, restore_new_process, restore_with_client};
 
 /// Re-export API types for convenience.
 pub use fc_api::types;
 
 /// Re-export the low-level API client for advanced use cases.
 pub use fc_api::Client;
+
+/// Re-export the token accepted by the `*_cancellable` methods.
+pub use tokio_util::sync::CancellationToken;
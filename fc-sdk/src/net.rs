@@ -0,0 +1,228 @@
+//! Host-side tap devices for microVM networking.
+//!
+//! Firecracker attaches a guest network interface to an existing host tap
+//! device (see [`NetworkInterface::host_dev_name`]). These helpers create and
+//! remove persistent taps through `/dev/net/tun`, the same interface
+//! `ip tuntap add mode tap` uses, and can enslave them to a bridge. All of
+//! them require `CAP_NET_ADMIN`.
+//!
+//! ```no_run
+//! use fc_sdk::net::TapGuard;
+//!
+//! # async fn example() -> fc_sdk::Result<()> {
+//! let tap = TapGuard::create("fc-tap0")?;
+//! tap.attach_to_bridge("br0")?;
+//!
+//! let vm = fc_sdk::VmBuilder::new("/tmp/firecracker.sock")
+//!     .network_interface_with_tap("eth0", &tap)
+//!     // ...
+//!     .start()
+//!     .await?;
+//! // `tap` is deleted when dropped, after the microVM is gone.
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`NetworkInterface::host_dev_name`]: fc_api::types::NetworkInterface::host_dev_name
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::error::{Error, Result};
+
+/// `SIOCBRADDIF` from `linux/sockios.h`; not exported by `libc` on Linux.
+const SIOCBRADDIF: libc::Ioctl = 0x89a2;
+
+/// Create a persistent tap device named `name` and bring it up.
+///
+/// The device survives this process; remove it with [`delete_tap()`] or use a
+/// [`TapGuard`]. Fails with an `EBUSY` [`Error::Io`] if a device named `name`
+/// already exists, rather than taking it over.
+pub fn create_tap(name: &str) -> Result<()> {
+    set_persist(name, libc::IFF_TUN_EXCL, true)?;
+    set_up(name).inspect_err(|_| {
+        set_persist(name, 0, false).ok();
+    })
+}
+
+/// Delete a persistent tap device created with [`create_tap()`].
+///
+/// Fails with a [`NotFound`](io::ErrorKind::NotFound) [`Error::Io`] if no
+/// device named `name` exists.
+pub fn delete_tap(name: &str) -> Result<()> {
+    // TUNSETIFF would otherwise create a device just to delete it again.
+    if_index(name)?;
+    set_persist(name, 0, false)
+}
+
+/// Add the network device `name` to the bridge `bridge`.
+pub fn attach_to_bridge(name: &str, bridge: &str) -> Result<()> {
+    let index = if_index(name)?;
+    let mut req = ifreq(bridge)?;
+    req.ifr_ifru.ifru_ifindex = index as libc::c_int;
+    ioctl(&control_socket()?, SIOCBRADDIF, &mut req)
+}
+
+/// A tap device that is deleted when dropped.
+#[derive(Debug)]
+pub struct TapGuard {
+    name: String,
+}
+
+impl TapGuard {
+    /// Create the tap device `name` with [`create_tap()`].
+    pub fn create(name: impl Into<String>) -> Result<Self> {
+        let name = name.into();
+        create_tap(&name)?;
+        Ok(Self { name })
+    }
+
+    /// Name of the host device, as passed to
+    /// [`NetworkInterface::host_dev_name`](fc_api::types::NetworkInterface::host_dev_name).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Add this tap to the bridge `bridge`.
+    pub fn attach_to_bridge(&self, bridge: &str) -> Result<()> {
+        attach_to_bridge(&self.name, bridge)
+    }
+}
+
+impl Drop for TapGuard {
+    fn drop(&mut self) {
+        // Best-effort cleanup.
+        delete_tap(&self.name).ok();
+    }
+}
+
+/// Attach to the tap `name` with the extra TUNSETIFF `flags` and set its
+/// persistence.
+fn set_persist(name: &str, flags: libc::c_int, persist: bool) -> Result<()> {
+    let tun: File = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_CLOEXEC)
+        .open("/dev/net/tun")?;
+    let mut req = ifreq(name)?;
+    req.ifr_ifru.ifru_flags = (libc::IFF_TAP | libc::IFF_NO_PI | flags) as libc::c_short;
+    ioctl(&tun, libc::TUNSETIFF, &mut req)?;
+
+    // SAFETY: `tun` is a valid tun descriptor attached to the device.
+    let ret = unsafe {
+        libc::ioctl(
+            tun.as_raw_fd(),
+            libc::TUNSETPERSIST,
+            libc::c_ulong::from(persist),
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+fn set_up(name: &str) -> Result<()> {
+    let socket = control_socket()?;
+    let mut req = ifreq(name)?;
+    ioctl(&socket, libc::SIOCGIFFLAGS as _, &mut req)?;
+    // SAFETY: SIOCGIFFLAGS filled in the flags member.
+    unsafe { req.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short };
+    ioctl(&socket, libc::SIOCSIFFLAGS as _, &mut req)
+}
+
+/// Index of the network device `name`.
+fn if_index(name: &str) -> Result<libc::c_uint> {
+    let c_name = std::ffi::CString::new(name)
+        .map_err(|_| Error::InvalidConfig(format!("invalid interface name: {name:?}")))?;
+    // SAFETY: `c_name` is a valid NUL-terminated string.
+    match unsafe { libc::if_nametoindex(c_name.as_ptr()) } {
+        0 => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no network device named {name:?}"),
+        )
+        .into()),
+        index => Ok(index),
+    }
+}
+
+/// A datagram socket to issue interface ioctls on.
+fn control_socket() -> Result<OwnedFd> {
+    // SAFETY: plain socket(2) call; the result is checked below.
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: `fd` is a freshly created descriptor owned by nobody else.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn ifreq(name: &str) -> Result<libc::ifreq> {
+    if name.is_empty() || name.len() >= libc::IFNAMSIZ || name.contains(['\0', '/']) {
+        return Err(Error::InvalidConfig(format!(
+            "invalid interface name: {name:?}"
+        )));
+    }
+    // SAFETY: ifreq is plain old data; all-zero is a valid value.
+    let mut req: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, &src) in req.ifr_name.iter_mut().zip(name.as_bytes()) {
+        *dst = src as libc::c_char;
+    }
+    Ok(req)
+}
+
+fn ioctl(fd: &impl AsRawFd, request: libc::Ioctl, req: &mut libc::ifreq) -> Result<()> {
+    // SAFETY: `req` is a valid ifreq for the duration of the call.
+    if unsafe { libc::ioctl(fd.as_raw_fd(), request, req as *mut libc::ifreq) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interface_name_validation() {
+        assert!(ifreq("tap0").is_ok());
+        assert!(ifreq("").is_err());
+        assert!(ifreq("a-name-that-is-too-long").is_err());
+        assert!(ifreq("bad/name").is_err());
+    }
+
+    fn io_kind(result: Result<()>) -> Option<io::ErrorKind> {
+        match result {
+            Err(Error::Io(e)) => Some(e.kind()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    #[ignore = "requires CAP_NET_ADMIN"]
+    fn test_create_tap_rejects_existing_device() {
+        let tap = TapGuard::create("fc-sdk-excl0").unwrap();
+        assert_eq!(
+            io_kind(create_tap(tap.name())),
+            Some(io::ErrorKind::ResourceBusy)
+        );
+        assert!(matches!(TapGuard::create(tap.name()), Err(Error::Io(_))));
+
+        // The failed attempts left the original device alone.
+        assert!(if_index(tap.name()).is_ok());
+        drop(tap);
+        assert!(if_index("fc-sdk-excl0").is_err());
+    }
+
+    #[test]
+    #[ignore = "requires CAP_NET_ADMIN"]
+    fn test_delete_missing_tap() {
+        assert_eq!(
+            io_kind(delete_tap("fc-sdk-none0")),
+            Some(io::ErrorKind::NotFound)
+        );
+        assert!(if_index("fc-sdk-none0").is_err());
+    }
+}
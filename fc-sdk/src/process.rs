@@ -20,14 +20,7 @@
 //!         boot_args: Some("console=ttyS0".into()),
 //!         initrd_path: None,
 //!     })
-//!     .machine_config(fc_sdk::types::MachineConfiguration {
-//!         vcpu_count: std::num::NonZeroU64::new(2).unwrap(),
-//!         mem_size_mib: 256,
-//!         smt: false,
-//!         track_dirty_pages: false,
-//!         cpu_template: None,
-//!         huge_pages: None,
-//!     })
+//!     .machine_config_simple(std::num::NonZeroU64::new(2).unwrap(), 256)
 //!     .start()
 //!     .await?;
 //! # Ok(())
@@ -56,55 +49,230 @@
 //!         boot_args: Some("console=ttyS0".into()),
 //!         initrd_path: None,
 //!     })
-//!     .machine_config(fc_sdk::types::MachineConfiguration {
-//!         vcpu_count: std::num::NonZeroU64::new(2).unwrap(),
-//!         mem_size_mib: 256,
-//!         smt: false,
-//!         track_dirty_pages: false,
-//!         cpu_template: None,
-//!         huge_pages: None,
-//!     })
+//!     .machine_config_simple(std::num::NonZeroU64::new(2).unwrap(), 256)
 //!     .start()
 //!     .await?;
 //! # Ok(())
 //! # }
 //! ```
 
+use std::fmt;
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use tokio::process::{Child, Command};
+use fc_api::types::{LoggerLevel, SnapshotLoadParams};
+use tokio::process::{Child, ChildStdin, Command};
 use tokio::time::{sleep, timeout as tokio_timeout};
 
 use crate::builder::VmBuilder;
 use crate::error::{Error, Result};
+use crate::restore::RestoreBuilder;
+use crate::vm::Vm;
 
 // =============================================================================
 // Socket Polling
 // =============================================================================
 
+/// Lower bound applied to socket poll intervals, so a zero interval cannot
+/// turn the wait into a busy loop.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Reject a zero socket poll interval configured on a builder.
+fn check_poll_interval(interval: Duration) -> Result<()> {
+    if interval.is_zero() {
+        return Err(Error::InvalidConfig(
+            "socket_poll_interval must be greater than zero".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Wait until the API socket accepts connections.
+///
+/// If `child` is given, fails early with [`Error::ProcessExited`] once it exits,
+/// and reports its PID in [`Error::SocketTimeout`] if it is still running.
+/// `poll_interval` is raised to [`MIN_POLL_INTERVAL`] if shorter.
 async fn wait_for_socket(
     path: &Path,
     timeout_duration: Duration,
     poll_interval: Duration,
+    mut child: Option<&mut Child>,
 ) -> Result<()> {
     let path = path.to_owned();
+    let pid = child.as_ref().and_then(|child| child.id());
+    let poll_interval = poll_interval.max(MIN_POLL_INTERVAL);
     tokio_timeout(timeout_duration, async {
         loop {
             if path.exists() && tokio::net::UnixStream::connect(&path).await.is_ok() {
                 return Ok(());
             }
+            if let Some(child) = child.as_deref_mut()
+                && let Some(status) = child.try_wait()?
+            {
+                return Err(Error::ProcessExited(Some(status)));
+            }
             sleep(poll_interval).await;
         }
     })
     .await
-    .map_err(|_| Error::SocketTimeout(path))?
+    .map_err(|_| Error::SocketTimeout { path, pid })?
+}
+
+/// [`wait_for_socket`], then wait until the HTTP API answers
+/// `GET /version`.
+///
+/// Firecracker may accept connections a moment before it serves requests.
+/// Both waits share `timeout_duration`; exceeding it is reported as
+/// [`Error::SocketTimeout`].
+async fn wait_for_api_ready(
+    path: &Path,
+    timeout_duration: Duration,
+    poll_interval: Duration,
+    mut child: Option<&mut Child>,
+) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + timeout_duration;
+    wait_for_socket(path, timeout_duration, poll_interval, child.as_deref_mut()).await?;
+
+    let client = crate::connection::connect(path);
+    let pid = child.as_ref().and_then(|child| child.id());
+    let poll_interval = poll_interval.max(MIN_POLL_INTERVAL);
+    tokio::time::timeout_at(deadline, async {
+        loop {
+            match client.get_firecracker_version().send().await {
+                Ok(_) => return Ok(()),
+                Err(err) => {
+                    let err = Error::from(err);
+                    if !err.is_retryable() {
+                        return Err(err);
+                    }
+                }
+            }
+            if let Some(child) = child.as_deref_mut()
+                && let Some(status) = child.try_wait()?
+            {
+                return Err(Error::ProcessExited(Some(status)));
+            }
+            sleep(poll_interval).await;
+        }
+    })
+    .await
+    .map_err(|_| Error::SocketTimeout {
+        path: path.to_owned(),
+        pid,
+    })?
+}
+
+/// [`wait_for_socket`] for a process we may not own, reporting `pid` on timeout.
+async fn await_socket(
+    path: &Path,
+    pid: Option<u32>,
+    timeout_duration: Duration,
+    poll_interval: Duration,
+) -> Result<()> {
+    wait_for_socket(path, timeout_duration, poll_interval, None)
+        .await
+        .map_err(|e| match e {
+            Error::SocketTimeout { path, .. } => Error::SocketTimeout { path, pid },
+            e => e,
+        })
+}
+
+/// Interval between reads while following a log file that has no new lines.
+#[cfg(feature = "stream")]
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether `pid` is running, i.e. exists and is not a zombie.
+#[cfg(feature = "stream")]
+fn process_alive(pid: u32) -> bool {
+    // The state follows the parenthesized command name, which may contain spaces.
+    std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat.rsplit_once(')')
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .is_some_and(|state| state != "Z" && state != "X")
+    })
+}
+
+// =============================================================================
+// Log Level
+// =============================================================================
+
+/// Firecracker log level.
+///
+/// Displays with the casing Firecracker expects for `--level` and the
+/// logger API; parsing is case-insensitive and also accepts `warn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Off => "Off",
+            Self::Error => "Error",
+            Self::Warn => "Warning",
+            Self::Info => "Info",
+            Self::Debug => "Debug",
+            Self::Trace => "Trace",
+        })
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "error" => Ok(Self::Error),
+            "warn" | "warning" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(Error::InvalidConfig(format!("invalid log level: {s}"))),
+        }
+    }
+}
+
+impl From<LogLevel> for LoggerLevel {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => Self::Off,
+            LogLevel::Error => Self::Error,
+            LogLevel::Warn => Self::Warning,
+            LogLevel::Info => Self::Info,
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Trace => Self::Trace,
+        }
+    }
 }
 
 // =============================================================================
 // FirecrackerProcessBuilder
 // =============================================================================
 
+/// Generate a socket path that is unique across builders in this process.
+///
+/// Format: `{std::env::temp_dir()}/fc-{pid}-{nanos}-{seq}.sock`.
+pub(crate) fn unique_socket_path() -> PathBuf {
+    static SEQ: AtomicU64 = AtomicU64::new(0);
+    let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!("fc-{}-{nanos}-{seq}.sock", std::process::id()))
+}
+
 /// Builder for spawning a Firecracker process directly.
 pub struct FirecrackerProcessBuilder {
     firecracker_bin: PathBuf,
@@ -121,9 +289,17 @@ pub struct FirecrackerProcessBuilder {
     http_api_max_payload_size: Option<usize>,
     mmds_size_limit: Option<usize>,
     enable_pci: Option<bool>,
+    extra_args: Vec<String>,
+    serial_input: bool,
+    stdin: Option<Stdio>,
+    stdout: Option<Stdio>,
+    stderr: Option<Stdio>,
+    current_dir: Option<PathBuf>,
     socket_timeout: Duration,
     socket_poll_interval: Duration,
+    wait_api_ready: bool,
     cleanup_socket: bool,
+    cleanup_socket_on_drop: Option<bool>,
 }
 
 impl FirecrackerProcessBuilder {
@@ -144,13 +320,45 @@ impl FirecrackerProcessBuilder {
             http_api_max_payload_size: None,
             mmds_size_limit: None,
             enable_pci: None,
+            extra_args: Vec::new(),
+            serial_input: false,
+            stdin: None,
+            stdout: None,
+            stderr: None,
+            current_dir: None,
             socket_timeout: Duration::from_secs(5),
             socket_poll_interval: Duration::from_millis(50),
+            wait_api_ready: false,
             cleanup_socket: true,
+            cleanup_socket_on_drop: None,
         }
     }
 
-    /// Set the VM identifier.
+    /// Create a new builder with a freshly generated, unique socket path.
+    ///
+    /// The socket is placed in [`std::env::temp_dir()`]; use
+    /// [`socket_dir()`](Self::socket_dir) to choose a different parent directory.
+    pub fn new_auto(firecracker_bin: impl Into<PathBuf>) -> Self {
+        Self::new(firecracker_bin, unique_socket_path())
+    }
+
+    /// Move the API socket into `dir`, keeping its file name.
+    pub fn socket_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        if let Some(file_name) = self.socket_path.file_name() {
+            self.socket_path = dir.as_ref().join(file_name);
+        }
+        self
+    }
+
+    /// Path to the Firecracker API socket this builder will use.
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Set the VM identifier (`--id`).
+    ///
+    /// Reported as the `id` of [`Vm::describe()`]; it cannot be changed
+    /// through the API. Firecracker uses `anonymous-instance` if unset.
     pub fn id(mut self, id: impl Into<String>) -> Self {
         self.id = Some(id.into());
         self
@@ -181,11 +389,19 @@ impl FirecrackerProcessBuilder {
     }
 
     /// Set the log level.
+    ///
+    /// Prefer [`log_level_typed()`](Self::log_level_typed); this passes the
+    /// string through unchecked for levels newer than [`LogLevel`].
     pub fn log_level(mut self, level: impl Into<String>) -> Self {
         self.log_level = Some(level.into());
         self
     }
 
+    /// Set the log level from a [`LogLevel`].
+    pub fn log_level_typed(self, level: LogLevel) -> Self {
+        self.log_level(level.to_string())
+    }
+
     /// Show the log level in output.
     pub fn show_level(mut self, show: bool) -> Self {
         self.show_level = Some(show);
@@ -222,6 +438,16 @@ impl FirecrackerProcessBuilder {
         self
     }
 
+    /// Set the working directory of the Firecracker process.
+    ///
+    /// Firecracker resolves relative paths (log, metrics, and those sent over
+    /// the API) against it. A relative socket path is resolved against it too.
+    /// By default the current directory of this process is inherited.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
     /// Set the timeout for waiting for the socket to become available.
     pub fn socket_timeout(mut self, timeout: Duration) -> Self {
         self.socket_timeout = timeout;
@@ -229,17 +455,104 @@ impl FirecrackerProcessBuilder {
     }
 
     /// Set the polling interval when waiting for the socket.
+    ///
+    /// Must be non-zero; `spawn()` returns [`Error::InvalidConfig`] otherwise.
     pub fn socket_poll_interval(mut self, interval: Duration) -> Self {
         self.socket_poll_interval = interval;
         self
     }
 
+    /// After the socket accepts connections, also wait for the API to answer
+    /// `GET /version` before `spawn()` returns.
+    ///
+    /// Closes the short window in which Firecracker accepts connections but
+    /// does not serve requests yet, which can fail the first configuration
+    /// request. Counts against the [socket timeout](Self::socket_timeout).
+    /// Disabled by default.
+    pub fn wait_api_ready(mut self, enable: bool) -> Self {
+        self.wait_api_ready = enable;
+        self
+    }
+
+    /// Append a raw argument after the ones generated from other options.
+    ///
+    /// An escape hatch for Firecracker flags this builder does not cover yet.
+    pub fn extra_arg(mut self, arg: impl Into<String>) -> Self {
+        self.extra_args.push(arg.into());
+        self
+    }
+
+    /// Append raw arguments; see [`extra_arg()`](Self::extra_arg).
+    pub fn extra_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Pipe the process's standard input so the guest serial console can be
+    /// written to via [`FirecrackerProcess::serial_writer()`].
+    ///
+    /// Firecracker forwards its standard input to the serial device. By
+    /// default it is inherited from this process.
+    pub fn serial_input(mut self, enable: bool) -> Self {
+        self.serial_input = enable;
+        self
+    }
+
+    /// Set the standard input of the process.
+    ///
+    /// Ignored when [`serial_input()`](Self::serial_input) is enabled. By
+    /// default it is inherited from this process.
+    pub fn stdin(mut self, stdin: impl Into<Stdio>) -> Self {
+        self.stdin = Some(stdin.into());
+        self
+    }
+
+    /// Set the standard output of the process.
+    ///
+    /// Firecracker writes its log there unless a
+    /// [`log_path()`](Self::log_path) is set, as well as guest serial output.
+    /// By default it is inherited from this process.
+    pub fn stdout(mut self, stdout: impl Into<Stdio>) -> Self {
+        self.stdout = Some(stdout.into());
+        self
+    }
+
+    /// Set the standard error of the process.
+    ///
+    /// By default it is inherited from this process.
+    pub fn stderr(mut self, stderr: impl Into<Stdio>) -> Self {
+        self.stderr = Some(stderr.into());
+        self
+    }
+
+    /// Connect standard input, output and error to `/dev/null`.
+    ///
+    /// Useful under a supervisor that must not share its descriptors with
+    /// Firecracker. Standard input stays piped with
+    /// [`serial_input()`](Self::serial_input).
+    pub fn null_stdio(self) -> Self {
+        self.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+    }
+
     /// Whether to clean up an existing socket file before spawning.
     pub fn cleanup_socket(mut self, cleanup: bool) -> Self {
         self.cleanup_socket = cleanup;
         self
     }
 
+    /// Whether to remove the socket file when the process handle is dropped.
+    ///
+    /// By default the socket is removed when a [`FirecrackerProcess`] is
+    /// dropped, but not when a [`DetachedFirecrackerProcess`] is. Setting this
+    /// explicitly applies the same choice to both handles, so a detached
+    /// process handed to another owner can still clean up after itself.
+    pub fn cleanup_socket_on_drop(mut self, cleanup: bool) -> Self {
+        self.cleanup_socket_on_drop = Some(cleanup);
+        self
+    }
+
     /// Build the command-line arguments for the Firecracker process.
     fn build_args(&self) -> Vec<String> {
         let mut args = vec![
@@ -302,44 +615,80 @@ impl FirecrackerProcessBuilder {
             args.push("--enable-pci".to_owned());
         }
 
+        args.extend(self.extra_args.iter().cloned());
+
         args
     }
 
     /// Spawn the Firecracker process and wait for the socket to become available.
-    pub async fn spawn(self) -> Result<FirecrackerProcess> {
+    pub async fn spawn(mut self) -> Result<FirecrackerProcess> {
+        check_poll_interval(self.socket_poll_interval)?;
+
+        if let Some(dir) = &self.current_dir
+            && self.socket_path.is_relative()
+        {
+            self.socket_path = dir.join(&self.socket_path);
+        }
+
         if self.cleanup_socket && self.socket_path.exists() {
             std::fs::remove_file(&self.socket_path).ok();
         }
 
-        let child = Command::new(&self.firecracker_bin)
-            .args(self.build_args())
-            .spawn()
-            .map_err(Error::SpawnFailed)?;
+        let mut command = Command::new(&self.firecracker_bin);
+        command.args(self.build_args());
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        if self.serial_input {
+            command.stdin(Stdio::piped());
+        } else if let Some(stdin) = self.stdin.take() {
+            command.stdin(stdin);
+        }
+        if let Some(stdout) = self.stdout.take() {
+            command.stdout(stdout);
+        }
+        if let Some(stderr) = self.stderr.take() {
+            command.stderr(stderr);
+        }
+        let child = command.spawn().map_err(Error::SpawnFailed)?;
 
         let pid = child.id();
         let socket_path = self.socket_path.clone();
+        let log_path = match (&self.current_dir, &self.log_path) {
+            (Some(dir), Some(path)) => Some(dir.join(path)),
+            (None, path) => path.clone(),
+            (_, None) => None,
+        };
 
         let mut process = FirecrackerProcess {
             child: Some(child),
             pid,
             socket_path,
-            cleanup_socket_on_drop: true,
+            cleanup_socket_on_drop: self.cleanup_socket_on_drop.unwrap_or(true),
+            cleanup_socket_after_detach: self.cleanup_socket_on_drop.unwrap_or(false),
+            jailer_layout: None,
+            log_path,
+            current_dir: self.current_dir,
+            serial_path: None,
         };
 
-        if let Err(e) = wait_for_socket(
-            &self.socket_path,
-            self.socket_timeout,
-            self.socket_poll_interval,
-        )
-        .await
-        {
-            // If socket wait failed, check if process exited
-            if let Some(child) = &mut process.child
-                && let Ok(Some(status)) = child.try_wait()
-            {
-                return Err(Error::ProcessExited(Some(status)));
-            }
-            return Err(e);
+        // On failure, dropping `process` kills a child that is still running.
+        if self.wait_api_ready {
+            wait_for_api_ready(
+                &self.socket_path,
+                self.socket_timeout,
+                self.socket_poll_interval,
+                process.child.as_mut(),
+            )
+            .await?;
+        } else {
+            wait_for_socket(
+                &self.socket_path,
+                self.socket_timeout,
+                self.socket_poll_interval,
+                process.child.as_mut(),
+            )
+            .await?;
         }
 
         Ok(process)
@@ -350,6 +699,9 @@ impl FirecrackerProcessBuilder {
 // JailerProcessBuilder
 // =============================================================================
 
+/// `cpu.max` period used by [`JailerProcessBuilder::cpu_quota()`].
+const CPU_MAX_PERIOD_US: u64 = 100_000;
+
 /// Builder for spawning a Firecracker process via the Jailer.
 pub struct JailerProcessBuilder {
     jailer_bin: PathBuf,
@@ -366,6 +718,7 @@ pub struct JailerProcessBuilder {
     cgroup_version: Option<String>,
     parent_cgroup: Option<String>,
     firecracker_args: Vec<String>,
+    stage_exec_file: bool,
     socket_timeout: Duration,
     socket_poll_interval: Duration,
 }
@@ -394,6 +747,7 @@ impl JailerProcessBuilder {
             cgroup_version: None,
             parent_cgroup: None,
             firecracker_args: Vec::new(),
+            stage_exec_file: false,
             socket_timeout: Duration::from_secs(5),
             socket_poll_interval: Duration::from_millis(50),
         }
@@ -417,6 +771,17 @@ impl JailerProcessBuilder {
         self
     }
 
+    /// Copy `exec_file` into the chroot before spawning (default: `false`).
+    ///
+    /// The binary is placed at `{chroot_root}/{exec_name}` and chowned to the
+    /// jailer uid/gid. The jailer copies the binary itself at startup, so this
+    /// is only needed in hardened setups that expect the chroot, binary
+    /// included, to be fully prepared before the jailer runs.
+    pub fn stage_exec_file(mut self, stage: bool) -> Self {
+        self.stage_exec_file = stage;
+        self
+    }
+
     /// Enable new PID namespace.
     pub fn new_pid_ns(mut self, new_pid_ns: bool) -> Self {
         self.new_pid_ns = new_pid_ns;
@@ -429,6 +794,30 @@ impl JailerProcessBuilder {
         self
     }
 
+    /// Limit CPU time to `percent` of one CPU (e.g. `150` for 1.5 CPUs).
+    ///
+    /// Adds a cgroup v2 `cpu.max` setting and sets the cgroup version to 2.
+    pub fn cpu_quota(self, percent: u32) -> Self {
+        let quota_us = u64::from(percent) * CPU_MAX_PERIOD_US / 100;
+        self.cgroup_version("2")
+            .cgroup(format!("cpu.max={quota_us} {CPU_MAX_PERIOD_US}"))
+    }
+
+    /// Limit memory usage to `bytes`.
+    ///
+    /// Adds a cgroup v2 `memory.max` setting and sets the cgroup version to 2.
+    pub fn memory_limit_bytes(self, bytes: u64) -> Self {
+        self.cgroup_version("2")
+            .cgroup(format!("memory.max={bytes}"))
+    }
+
+    /// Limit the number of processes and threads to `max`.
+    ///
+    /// Adds a cgroup v2 `pids.max` setting and sets the cgroup version to 2.
+    pub fn pids_max(self, max: u64) -> Self {
+        self.cgroup_version("2").cgroup(format!("pids.max={max}"))
+    }
+
     /// Add a resource limit (e.g., `"fsize=2048"`).
     pub fn resource_limit(mut self, limit: impl Into<String>) -> Self {
         self.resource_limits.push(limit.into());
@@ -460,6 +849,8 @@ impl JailerProcessBuilder {
     }
 
     /// Set the polling interval when waiting for the socket.
+    ///
+    /// Must be non-zero; `spawn()` returns [`Error::InvalidConfig`] otherwise.
     pub fn socket_poll_interval(mut self, interval: Duration) -> Self {
         self.socket_poll_interval = interval;
         self
@@ -469,17 +860,81 @@ impl JailerProcessBuilder {
     ///
     /// Returns `{chroot_base_dir}/{exec_name}/{id}/root/run/firecracker.socket`.
     pub fn socket_path(&self) -> PathBuf {
-        let exec_name = self
-            .exec_file
-            .file_name()
-            .expect("exec_file must have a filename")
-            .to_string_lossy();
+        self.chroot_root().join("run").join("firecracker.socket")
+    }
+
+    /// Compute the host path of the PID file the jailer writes for this
+    /// microVM, `{chroot_root}/{exec_name}.pid`.
+    ///
+    /// Holds the PID of Firecracker when it is not a direct child, e.g. with
+    /// [`daemonize()`](Self::daemonize) or [`new_pid_ns()`](Self::new_pid_ns).
+    pub fn pid_file(&self) -> PathBuf {
+        self.chroot_root().join(format!("{}.pid", self.exec_name()))
+    }
+
+    /// Compute the host paths the jailer will create for this microVM.
+    pub fn layout(&self) -> JailerLayout {
+        let chroot_root = self.chroot_root();
+        JailerLayout {
+            id: self.id.clone(),
+            pid_file: self.pid_file(),
+            socket_path: self.socket_path(),
+            netns: self.netns.clone(),
+            chroot_root,
+        }
+    }
+
+    /// `{chroot_base_dir}/{exec_name}/{id}/root`
+    fn chroot_root(&self) -> PathBuf {
         self.chroot_base_dir
-            .join(exec_name.as_ref())
+            .join(self.exec_name())
             .join(&self.id)
             .join("root")
-            .join("run")
-            .join("firecracker.socket")
+    }
+
+    fn exec_name(&self) -> String {
+        self.exec_file
+            .file_name()
+            .expect("exec_file must have a filename")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Catch setup mistakes the jailer would only report as a bare non-zero
+    /// exit status.
+    fn validate(&self) -> Result<()> {
+        // `(uid_t)-1` means "unchanged" to setuid(2) and friends.
+        if self.uid == u32::MAX || self.gid == u32::MAX {
+            return Err(Error::InvalidConfig(format!(
+                "uid/gid {}:{} is not a valid jailer identity",
+                self.uid, self.gid
+            )));
+        }
+        check_poll_interval(self.socket_poll_interval)?;
+        if !self.exec_file.is_file() {
+            return Err(Error::InvalidConfig(format!(
+                "exec file {} does not exist",
+                self.exec_file.display()
+            )));
+        }
+        if let Some(netns) = &self.netns
+            && !Path::new(netns).exists()
+        {
+            return Err(Error::InvalidConfig(format!(
+                "netns {netns} does not exist; create it first (e.g. `ip netns add`)"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Copy `exec_file` into the chroot root and hand it to the jailer uid/gid.
+    fn stage_exec(&self) -> Result<()> {
+        let chroot_root = self.chroot_root();
+        std::fs::create_dir_all(&chroot_root)?;
+        let dest = chroot_root.join(self.exec_name());
+        std::fs::copy(&self.exec_file, &dest)?;
+        std::os::unix::fs::chown(&dest, Some(self.uid), Some(self.gid))?;
+        Ok(())
     }
 
     /// Build the command-line arguments for the Jailer process.
@@ -543,12 +998,21 @@ impl JailerProcessBuilder {
     }
 
     /// Spawn the Jailer process and wait for the Firecracker socket to become available.
+    ///
+    /// Before launching, checks that `exec_file` and the netns path exist and
+    /// that uid/gid are usable, returning [`Error::InvalidConfig`] otherwise.
     pub async fn spawn(self) -> Result<FirecrackerProcess> {
-        let socket_path = self.socket_path();
+        let layout = self.layout();
+        let socket_path = layout.socket_path.clone();
         let socket_timeout = self.socket_timeout;
         let socket_poll_interval = self.socket_poll_interval;
         let daemonize = self.daemonize;
 
+        self.validate()?;
+        if self.stage_exec_file {
+            self.stage_exec()?;
+        }
+
         let child = Command::new(&self.jailer_bin)
             .args(self.build_args())
             .spawn()
@@ -565,19 +1029,85 @@ impl JailerProcessBuilder {
             (Some(child), pid)
         };
 
-        let process = FirecrackerProcess {
+        let pid_file = layout.pid_file.clone();
+        let mut process = FirecrackerProcess {
             child,
             pid,
             socket_path: socket_path.clone(),
             cleanup_socket_on_drop: !daemonize,
+            cleanup_socket_after_detach: false,
+            jailer_layout: Some(layout),
+            log_path: None,
+            current_dir: None,
+            serial_path: None,
         };
 
-        wait_for_socket(&socket_path, socket_timeout, socket_poll_interval).await?;
+        match wait_for_socket(
+            &socket_path,
+            socket_timeout,
+            socket_poll_interval,
+            process.child.as_mut(),
+        )
+        .await
+        {
+            // A daemonized Firecracker is not our child; its PID is only
+            // known from the pidfile the jailer writes into the chroot.
+            Err(Error::SocketTimeout { path, pid: None }) => Err(Error::SocketTimeout {
+                path,
+                pid: std::fs::read_to_string(&pid_file)
+                    .ok()
+                    .and_then(|pid| pid.trim().parse().ok()),
+            }),
+            result => result,
+        }?;
 
         Ok(process)
     }
 }
 
+/// Host paths belonging to a jailed Firecracker process.
+///
+/// Lets supervisors clean up the per-VM chroot once the process has exited.
+/// The jailer does not remove any of these itself.
+#[derive(Debug, Clone)]
+pub struct JailerLayout {
+    /// The jailer `--id` of the microVM.
+    pub id: String,
+    /// Chroot directory, `{chroot_base_dir}/{exec_name}/{id}/root`.
+    pub chroot_root: PathBuf,
+    /// Firecracker API socket inside the chroot.
+    pub socket_path: PathBuf,
+    /// PID file written by the jailer inside the chroot.
+    pub pid_file: PathBuf,
+    /// Network namespace path passed via `--netns`, if any.
+    pub netns: Option<String>,
+}
+
+impl JailerLayout {
+    /// Read the Firecracker PID from [`pid_file`](Self::pid_file).
+    ///
+    /// Returns `Ok(None)` if the jailer has not written the file (yet).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read and [`Error::Other`]
+    /// if it does not contain a PID.
+    pub fn read_pid(&self) -> Result<Option<u32>> {
+        let contents = match std::fs::read_to_string(&self.pid_file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        contents.trim().parse().map(Some).map_err(|_| {
+            Error::Other(format!(
+                "invalid PID file {}: {:?}",
+                self.pid_file.display(),
+                contents.trim()
+            ))
+        })
+    }
+}
+
 // =============================================================================
 // FirecrackerProcess
 // =============================================================================
@@ -592,6 +1122,11 @@ pub struct FirecrackerProcess {
     pid: Option<u32>,
     socket_path: PathBuf,
     cleanup_socket_on_drop: bool,
+    cleanup_socket_after_detach: bool,
+    jailer_layout: Option<JailerLayout>,
+    log_path: Option<PathBuf>,
+    current_dir: Option<PathBuf>,
+    serial_path: Option<PathBuf>,
 }
 
 /// Metadata for a detached Firecracker process.
@@ -601,6 +1136,8 @@ pub struct FirecrackerProcess {
 pub struct DetachedFirecrackerProcess {
     pid: Option<u32>,
     socket_path: PathBuf,
+    cleanup_socket_on_drop: bool,
+    jailer_layout: Option<JailerLayout>,
 }
 
 impl DetachedFirecrackerProcess {
@@ -613,6 +1150,32 @@ impl DetachedFirecrackerProcess {
     pub fn socket_path(&self) -> &Path {
         &self.socket_path
     }
+
+    /// Jailer paths, if the process was spawned via [`JailerProcessBuilder`].
+    pub fn jailer_layout(&self) -> Option<&JailerLayout> {
+        self.jailer_layout.as_ref()
+    }
+
+    /// Read the Firecracker PID from the jailer's PID file.
+    ///
+    /// Returns `Ok(None)` if the process was not spawned via
+    /// [`JailerProcessBuilder`] or the PID file does not exist; see
+    /// [`JailerLayout::read_pid()`].
+    pub fn read_jailer_pid(&self) -> Result<Option<u32>> {
+        match &self.jailer_layout {
+            Some(layout) => layout.read_pid(),
+            None => Ok(None),
+        }
+    }
+
+    /// Wait until the API socket accepts connections again.
+    ///
+    /// Useful when re-adopting a detached process whose socket may have been
+    /// recreated (e.g. after restoring a snapshot). Intervals shorter than
+    /// 1ms are raised to 1ms.
+    pub async fn await_socket(&self, timeout: Duration, poll_interval: Duration) -> Result<()> {
+        await_socket(&self.socket_path, self.pid, timeout, poll_interval).await
+    }
 }
 
 impl FirecrackerProcess {
@@ -626,16 +1189,212 @@ impl FirecrackerProcess {
         &self.socket_path
     }
 
+    /// Jailer paths, if the process was spawned via [`JailerProcessBuilder`].
+    pub fn jailer_layout(&self) -> Option<&JailerLayout> {
+        self.jailer_layout.as_ref()
+    }
+
+    /// Read the Firecracker PID from the jailer's PID file.
+    ///
+    /// Unlike [`pid()`](Self::pid), this also works for a daemonized jailer,
+    /// where Firecracker is not a child of this process. Returns `Ok(None)`
+    /// if the process was not spawned via [`JailerProcessBuilder`] or the PID
+    /// file does not exist; see [`JailerLayout::read_pid()`].
+    pub fn read_jailer_pid(&self) -> Result<Option<u32>> {
+        match &self.jailer_layout {
+            Some(layout) => layout.read_pid(),
+            None => Ok(None),
+        }
+    }
+
+    /// Log file set via [`FirecrackerProcessBuilder::log_path()`], if any.
+    pub fn log_path(&self) -> Option<&Path> {
+        self.log_path.as_deref()
+    }
+
+    /// Host path of the guest serial console output, if the microVM was
+    /// started with [`start_vm()`](Self::start_vm) and a serial output path.
+    ///
+    /// For jailed processes the path is resolved inside the chroot.
+    pub fn serial_path(&self) -> Option<&Path> {
+        self.serial_path.as_deref()
+    }
+
+    /// Open the serial console output for reading.
+    ///
+    /// Opening a named pipe waits until Firecracker has opened it for
+    /// writing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingConfig`] if no serial output path is known;
+    /// see [`serial_path()`](Self::serial_path).
+    pub async fn serial_reader(&self) -> Result<tokio::fs::File> {
+        let path = self
+            .serial_path
+            .as_deref()
+            .ok_or(Error::MissingConfig("serial_out_path"))?;
+        Ok(tokio::fs::File::open(path).await?)
+    }
+
+    /// Take the pipe to the guest serial console input.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingConfig`] unless the process was spawned with
+    /// [`FirecrackerProcessBuilder::serial_input()`] and the pipe has not
+    /// been taken yet.
+    pub fn serial_writer(&mut self) -> Result<ChildStdin> {
+        self.child
+            .as_mut()
+            .and_then(|child| child.stdin.take())
+            .ok_or(Error::MissingConfig("serial_input"))
+    }
+
+    /// Wait until the API socket accepts connections again.
+    ///
+    /// Useful after restoring a snapshot onto a reused process. Unlike the
+    /// wait performed by `spawn()`, this does not watch for an early exit.
+    /// Intervals shorter than 1ms are raised to 1ms.
+    pub async fn await_socket(&self, timeout: Duration, poll_interval: Duration) -> Result<()> {
+        await_socket(&self.socket_path, self.pid, timeout, poll_interval).await
+    }
+
+    /// Follow the log file set via [`FirecrackerProcessBuilder::log_path()`],
+    /// like `tail -f`.
+    ///
+    /// Yields lines from the start of the file, waiting for the file to be
+    /// created if necessary. The stream ends once the process has exited and
+    /// the remaining lines have been read, or after yielding its first error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingConfig`] if no log path was configured on the
+    /// builder (always the case for jailed processes).
+    #[cfg(feature = "stream")]
+    pub fn tail_log(&self) -> Result<impl futures::Stream<Item = Result<String>> + use<>> {
+        use tokio::io::AsyncBufReadExt;
+
+        let path = self
+            .log_path
+            .clone()
+            .ok_or(Error::MissingConfig("log_path"))?;
+        let pid = self.pid;
+
+        struct Tail {
+            path: PathBuf,
+            pid: Option<u32>,
+            reader: Option<tokio::io::BufReader<tokio::fs::File>>,
+            line: String,
+        }
+
+        let tail = Tail {
+            path,
+            pid,
+            reader: None,
+            line: String::new(),
+        };
+        // `None` ends the stream.
+        Ok(futures::stream::unfold(Some(tail), |state| async move {
+            let mut tail = state?;
+            loop {
+                // Sample liveness before reading so lines written just
+                // before exit are still yielded.
+                let alive = tail.pid.is_some_and(process_alive);
+                let Some(reader) = &mut tail.reader else {
+                    match tokio::fs::File::open(&tail.path).await {
+                        Ok(file) => tail.reader = Some(tokio::io::BufReader::new(file)),
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound && alive => {
+                            sleep(LOG_POLL_INTERVAL).await;
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+                        Err(e) => return Some((Err(e.into()), None)),
+                    }
+                    continue;
+                };
+                match reader.read_line(&mut tail.line).await {
+                    Ok(_) if tail.line.ends_with('\n') => {
+                        let mut line = std::mem::take(&mut tail.line);
+                        line.pop();
+                        return Some((Ok(line), Some(tail)));
+                    }
+                    // At EOF, possibly with a partial line still being written.
+                    Ok(_) if alive => sleep(LOG_POLL_INTERVAL).await,
+                    Ok(_) if tail.line.is_empty() => return None,
+                    Ok(_) => return Some((Ok(std::mem::take(&mut tail.line)), None)),
+                    Err(e) => return Some((Err(e.into()), None)),
+                }
+            }
+        }))
+    }
+
     /// Create a [`VmBuilder`] connected to this process's socket.
     pub fn vm_builder(&self) -> VmBuilder {
         VmBuilder::new(&self.socket_path)
     }
 
+    /// Configure and boot a microVM on this process.
+    ///
+    /// `configure` receives [`vm_builder()`](Self::vm_builder) and returns the
+    /// builder to start. If configuration or boot fails, the process is killed
+    /// before the error is returned, so a failed start never leaks a
+    /// Firecracker process.
+    pub async fn start_vm(
+        mut self,
+        configure: impl FnOnce(VmBuilder) -> VmBuilder,
+    ) -> Result<RunningVm> {
+        let builder = configure(self.vm_builder());
+        self.serial_path = builder.serial_out_path().map(|path| self.host_path(path));
+        match builder.start().await {
+            Ok(vm) => Ok(RunningVm { process: self, vm }),
+            Err(Error::ProcessExited(None)) => {
+                // Reaping the exited process gives its real status.
+                Err(Error::ProcessExited(self.kill().await.ok().flatten()))
+            }
+            Err(e) => {
+                self.kill().await.ok();
+                Err(e)
+            }
+        }
+    }
+
+    /// Restore a microVM from a snapshot on this process.
+    ///
+    /// `configure` receives a [`RestoreBuilder`] for `params` connected to
+    /// this process and returns the builder to load, e.g. to add network
+    /// overrides. Like [`start_vm()`](Self::start_vm), the process is killed
+    /// if the restore fails, since it cannot be reused for another attempt.
+    pub async fn restore_vm(
+        mut self,
+        params: SnapshotLoadParams,
+        configure: impl FnOnce(RestoreBuilder) -> RestoreBuilder,
+    ) -> Result<RunningVm> {
+        let restore = configure(RestoreBuilder::with_client(self.client(), params));
+        match restore.load().await {
+            Ok(vm) => Ok(RunningVm { process: self, vm }),
+            Err(e) => {
+                self.kill().await.ok();
+                Err(e)
+            }
+        }
+    }
+
     /// Create a low-level API client connected to this process's socket.
     pub fn client(&self) -> fc_api::Client {
         crate::connection::connect(&self.socket_path)
     }
 
+    /// Resolve a path sent over the API to where it is on the host.
+    fn host_path(&self, path: &Path) -> PathBuf {
+        match (&self.jailer_layout, &self.current_dir) {
+            (Some(layout), _) => layout
+                .chroot_root
+                .join(path.strip_prefix("/").unwrap_or(path)),
+            (None, Some(dir)) => dir.join(path),
+            (None, None) => path.to_owned(),
+        }
+    }
+
     /// Gracefully shut down the Firecracker process (SIGTERM + wait).
     pub async fn shutdown(&mut self) -> Result<Option<std::process::ExitStatus>> {
         if let Some(ref mut child) = self.child {
@@ -678,30 +1437,221 @@ impl FirecrackerProcess {
         }
     }
 
+    /// Wait up to `timeout` for the Firecracker process to exit.
+    ///
+    /// Returns `Ok(None)` if it is still running when the timeout elapses;
+    /// the handle keeps owning the process, so this can be called in a loop.
+    /// Like [`wait()`](Self::wait), also returns `Ok(None)` once the process
+    /// has already been reaped, killed or detached through this handle.
+    pub async fn wait_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<std::process::ExitStatus>> {
+        let Some(child) = &mut self.child else {
+            return Ok(None);
+        };
+        // `Child::wait` is cancel safe, so timing out leaves the child intact.
+        match tokio_timeout(timeout, child.wait()).await {
+            Ok(status) => {
+                let status = status?;
+                self.child = None;
+                self.pid = None;
+                Ok(Some(status))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
     /// Detach this handle without terminating the underlying process.
     ///
     /// After detaching, dropping this handle will not kill the process or
-    /// remove the API socket path.
+    /// remove the API socket path. Dropping the returned handle removes the
+    /// socket only if requested via
+    /// [`FirecrackerProcessBuilder::cleanup_socket_on_drop()`].
     pub fn detach(mut self) -> DetachedFirecrackerProcess {
         let detached = DetachedFirecrackerProcess {
             pid: self.pid,
             socket_path: self.socket_path.clone(),
+            cleanup_socket_on_drop: self.cleanup_socket_after_detach,
+            jailer_layout: self.jailer_layout.take(),
         };
         self.child = None;
         self.pid = None;
         self.cleanup_socket_on_drop = false;
         detached
     }
+
+    /// Wrap this process in a [`ShutdownGuard`] for graceful shutdown at
+    /// scope exit.
+    ///
+    /// `Drop` cannot await, so dropping a process can only SIGKILL it. Call
+    /// [`ShutdownGuard::shutdown()`] on the way out to terminate it
+    /// gracefully instead; a guard dropped without it still kills the
+    /// process.
+    pub fn guard(self) -> ShutdownGuard {
+        ShutdownGuard {
+            process: self,
+            timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
 }
 
-impl Drop for FirecrackerProcess {
-    fn drop(&mut self) {
-        // Best-effort SIGKILL if the process is still running.
-        if let Some(pid) = self.pid {
+// =============================================================================
+// RunningVm
+// =============================================================================
+
+/// A booted microVM together with the Firecracker process serving it.
+///
+/// Returned by [`FirecrackerProcess::start_vm()`],
+/// [`FirecrackerProcess::restore_vm()`] and
+/// [`restore_new_process()`](crate::restore_new_process). Dereferences to
+/// [`Vm`], so API methods can be called directly. Dropping it kills the
+/// process, like dropping a [`FirecrackerProcess`].
+pub struct RunningVm {
+    process: FirecrackerProcess,
+    vm: Vm,
+}
+
+impl RunningVm {
+    /// The Firecracker process serving this microVM.
+    pub fn process(&self) -> &FirecrackerProcess {
+        &self.process
+    }
+
+    /// The API handle for this microVM.
+    pub fn vm(&self) -> &Vm {
+        &self.vm
+    }
+
+    /// Split into the process and VM handles.
+    pub fn into_parts(self) -> (FirecrackerProcess, Vm) {
+        (self.process, self.vm)
+    }
+
+    /// Shut down the guest, then the Firecracker process.
+    ///
+    /// Asks the guest to stop via [`Vm::shutdown_and_wait()`] and then
+    /// terminates the process with [`FirecrackerProcess::shutdown()`]. If the
+    /// guest does not stop within `timeout`, the process is killed and the
+    /// error is returned.
+    pub async fn shutdown(mut self, timeout: Duration) -> Result<Option<std::process::ExitStatus>> {
+        if let Err(e) = self.vm.shutdown_and_wait(timeout).await {
+            self.process.kill().await.ok();
+            return Err(e);
+        }
+        self.process.shutdown().await
+    }
+}
+
+impl Deref for RunningVm {
+    type Target = Vm;
+
+    fn deref(&self) -> &Vm {
+        &self.vm
+    }
+}
+
+// =============================================================================
+// ShutdownGuard
+// =============================================================================
+
+/// How long [`ShutdownGuard::shutdown()`] waits after SIGTERM by default.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A [`FirecrackerProcess`] that is shut down gracefully on request.
+///
+/// Returned by [`FirecrackerProcess::guard()`]. Dereferences to the process.
+/// [`shutdown()`](Self::shutdown) sends SIGTERM and escalates to SIGKILL
+/// after a timeout; if the guard is dropped instead, the process is killed
+/// as usual.
+///
+/// ```no_run
+/// use fc_sdk::FirecrackerProcessBuilder;
+///
+/// # async fn example() -> fc_sdk::Result<()> {
+/// let process = FirecrackerProcessBuilder::new_auto("/usr/bin/firecracker")
+///     .spawn()
+///     .await?
+///     .guard();
+/// let vm = process.vm_builder()
+///     // ...
+///     .start()
+///     .await?;
+/// // ...
+/// process.shutdown().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ShutdownGuard {
+    process: FirecrackerProcess,
+    timeout: Duration,
+}
+
+impl ShutdownGuard {
+    /// Set how long [`shutdown()`](Self::shutdown) waits for the process to
+    /// exit after SIGTERM before killing it (default: 5 seconds).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send SIGTERM and wait for the process to exit, sending SIGKILL if it
+    /// is still running after the timeout.
+    ///
+    /// Returns `Ok(None)` if the process was already reaped or detached.
+    pub async fn shutdown(mut self) -> Result<Option<std::process::ExitStatus>> {
+        if self.process.child.is_some()
+            && let Some(pid) = self.process.pid
+        {
+            // SAFETY: plain kill(2) call; the child has not been reaped, so
+            // `pid` still refers to it.
             unsafe {
-                libc::kill(pid as i32, libc::SIGKILL);
+                libc::kill(pid as i32, libc::SIGTERM);
             }
         }
+        match self.process.wait_timeout(self.timeout).await? {
+            Some(status) => Ok(Some(status)),
+            None => self.process.kill().await,
+        }
+    }
+
+    /// Release the process without shutting it down.
+    pub fn into_inner(self) -> FirecrackerProcess {
+        self.process
+    }
+}
+
+impl Deref for ShutdownGuard {
+    type Target = FirecrackerProcess;
+
+    fn deref(&self) -> &FirecrackerProcess {
+        &self.process
+    }
+}
+
+impl DerefMut for ShutdownGuard {
+    fn deref_mut(&mut self) -> &mut FirecrackerProcess {
+        &mut self.process
+    }
+}
+
+impl Drop for FirecrackerProcess {
+    fn drop(&mut self) {
+        // Best-effort SIGKILL if the process is still running. Going through
+        // the child handle avoids signalling a PID that was already reaped
+        // (e.g. by a failed socket wait) and possibly reused.
+        if let Some(child) = &mut self.child {
+            child.start_kill().ok();
+        }
+        if self.cleanup_socket_on_drop {
+            // Best-effort socket cleanup.
+            std::fs::remove_file(&self.socket_path).ok();
+        }
+    }
+}
+
+impl Drop for DetachedFirecrackerProcess {
+    fn drop(&mut self) {
         if self.cleanup_socket_on_drop {
             // Best-effort socket cleanup.
             std::fs::remove_file(&self.socket_path).ok();
@@ -716,6 +1666,22 @@ impl Drop for FirecrackerProcess {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::TempDir;
+
+    /// A [`FirecrackerProcess`] wrapping `child`, with no socket or log files.
+    fn test_process(child: Child) -> FirecrackerProcess {
+        FirecrackerProcess {
+            pid: child.id(),
+            child: Some(child),
+            socket_path: unique_socket_path(),
+            cleanup_socket_on_drop: false,
+            cleanup_socket_after_detach: false,
+            jailer_layout: None,
+            log_path: None,
+            current_dir: None,
+            serial_path: None,
+        }
+    }
 
     #[test]
     fn test_firecracker_builder_args() {
@@ -754,6 +1720,18 @@ mod tests {
         assert!(args.contains(&"--enable-pci".to_owned()));
     }
 
+    #[test]
+    fn test_auto_socket_paths_are_unique() {
+        let a = FirecrackerProcessBuilder::new_auto("/usr/bin/firecracker");
+        let b = FirecrackerProcessBuilder::new_auto("/usr/bin/firecracker");
+        assert_ne!(a.socket_path(), b.socket_path());
+        assert!(a.socket_path().starts_with(std::env::temp_dir()));
+
+        let file_name = a.socket_path().file_name().unwrap().to_owned();
+        let a = a.socket_dir("/run/fc");
+        assert_eq!(a.socket_path(), Path::new("/run/fc").join(file_name));
+    }
+
     #[test]
     fn test_jailer_socket_path() {
         let builder = JailerProcessBuilder::new(
@@ -785,6 +1763,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_jailer_layout() {
+        let layout = JailerProcessBuilder::new(
+            "/usr/bin/jailer",
+            "/usr/bin/firecracker",
+            "my-vm",
+            1000,
+            1000,
+        )
+        .netns("/var/run/netns/my-vm")
+        .layout();
+        assert_eq!(layout.id, "my-vm");
+        assert_eq!(
+            layout.chroot_root,
+            PathBuf::from("/srv/jailer/firecracker/my-vm/root")
+        );
+        assert_eq!(
+            layout.pid_file,
+            PathBuf::from("/srv/jailer/firecracker/my-vm/root/firecracker.pid")
+        );
+        assert!(layout.socket_path.starts_with(&layout.chroot_root));
+        assert_eq!(layout.netns.as_deref(), Some("/var/run/netns/my-vm"));
+    }
+
+    #[test]
+    fn test_read_jailer_pid() {
+        let dir = TempDir::new("jailer-pid");
+        let layout = JailerProcessBuilder::new(
+            "/usr/bin/jailer",
+            "/usr/bin/firecracker",
+            "my-vm",
+            1000,
+            1000,
+        )
+        .chroot_base_dir(dir.path())
+        .layout();
+        assert_eq!(layout.read_pid().unwrap(), None);
+
+        std::fs::create_dir_all(&layout.chroot_root).unwrap();
+        std::fs::write(&layout.pid_file, "4242\n").unwrap();
+        assert_eq!(layout.read_pid().unwrap(), Some(4242));
+
+        let detached = DetachedFirecrackerProcess {
+            pid: None,
+            socket_path: layout.socket_path.clone(),
+            cleanup_socket_on_drop: false,
+            jailer_layout: Some(layout.clone()),
+        };
+        assert_eq!(detached.read_jailer_pid().unwrap(), Some(4242));
+
+        std::fs::write(&layout.pid_file, "garbage").unwrap();
+        assert!(matches!(layout.read_pid(), Err(Error::Other(_))));
+    }
+
     #[test]
     fn test_jailer_builder_args() {
         let builder = JailerProcessBuilder::new(
@@ -827,4 +1859,323 @@ mod tests {
         let separator_pos = args.iter().position(|a| a == "--").unwrap();
         assert_eq!(args[separator_pos + 1], "--no-seccomp");
     }
+
+    #[test]
+    fn test_log_level_round_trip() {
+        assert_eq!("debug".parse::<LogLevel>().unwrap(), LogLevel::Debug);
+        assert_eq!("warn".parse::<LogLevel>().unwrap(), LogLevel::Warn);
+        assert_eq!(LogLevel::Warn.to_string(), "Warning");
+        assert!("verbose".parse::<LogLevel>().is_err());
+
+        for level in [
+            LogLevel::Off,
+            LogLevel::Error,
+            LogLevel::Warn,
+            LogLevel::Trace,
+        ] {
+            assert_eq!(level.to_string(), LoggerLevel::from(level).to_string());
+        }
+
+        let args = FirecrackerProcessBuilder::new("firecracker", "/tmp/fc.sock")
+            .log_level_typed(LogLevel::Info)
+            .build_args();
+        assert!(args.windows(2).any(|w| w == ["--level", "Info"]));
+    }
+
+    #[test]
+    fn test_cgroup_v2_helpers() {
+        let args = JailerProcessBuilder::new("jailer", "firecracker", "vm", 1000, 1000)
+            .cpu_quota(150)
+            .memory_limit_bytes(512 * 1024 * 1024)
+            .pids_max(64)
+            .build_args();
+
+        assert!(
+            args.windows(2)
+                .any(|w| w == ["--cgroup", "cpu.max=150000 100000"])
+        );
+        assert!(
+            args.windows(2)
+                .any(|w| w == ["--cgroup", "memory.max=536870912"])
+        );
+        assert!(args.windows(2).any(|w| w == ["--cgroup", "pids.max=64"]));
+        assert!(args.windows(2).any(|w| w == ["--cgroup-version", "2"]));
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn test_tail_log_until_exit() {
+        use futures::StreamExt;
+
+        let dir = TempDir::new("tail");
+        let log_path = dir.join("fc.log");
+        std::fs::write(&log_path, "one\ntwo\npartial").unwrap();
+
+        let child = Command::new("sleep").arg("0.3").spawn().unwrap();
+        let mut process = test_process(child);
+        process.log_path = Some(log_path);
+        let lines: Vec<String> = process
+            .tail_log()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        drop(process);
+
+        assert_eq!(lines, ["one", "two", "partial"]);
+    }
+
+    #[tokio::test]
+    async fn test_start_vm_kills_process_on_error() {
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id().unwrap();
+        let process = test_process(child);
+
+        // No boot source: configuration fails before any API call.
+        let result = process.start_vm(|builder| builder).await;
+        assert!(matches!(result, Err(Error::MissingConfig("boot_source"))));
+        assert_eq!(unsafe { libc::kill(pid as i32, 0) }, -1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_vm_kills_process_on_error() {
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id().unwrap();
+        let process = test_process(child);
+        let params = SnapshotLoadParams {
+            snapshot_path: "/nonexistent/snapshot".into(),
+            mem_file_path: Some("/nonexistent/mem".into()),
+            mem_backend: None,
+            enable_diff_snapshots: None,
+            track_dirty_pages: None,
+            resume_vm: None,
+            network_overrides: vec![],
+        };
+
+        // The snapshot does not exist, so the load fails before any API call.
+        let result = process.restore_vm(params, |restore| restore).await;
+        assert!(matches!(result, Err(Error::Io(_))));
+        assert_eq!(unsafe { libc::kill(pid as i32, 0) }, -1);
+    }
+
+    #[tokio::test]
+    async fn test_running_vm_shutdown_kills_process_when_guest_unreachable() {
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id().unwrap();
+        let process = test_process(child);
+        let vm = Vm::from_client(crate::connection::connect(process.socket_path()));
+        let running = RunningVm { process, vm };
+
+        assert!(running.shutdown(Duration::from_millis(100)).await.is_err());
+        assert_eq!(unsafe { libc::kill(pid as i32, 0) }, -1);
+    }
+
+    #[test]
+    fn test_stage_exec_file() {
+        let dir = TempDir::new("stage-exec");
+        let exec_file = dir.join("firecracker");
+        std::fs::write(&exec_file, b"\x7fELF").unwrap();
+
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let builder = JailerProcessBuilder::new("jailer", &exec_file, "vm", uid, gid)
+            .chroot_base_dir(dir.join("jail"))
+            .stage_exec_file(true);
+        builder.stage_exec().unwrap();
+
+        let staged = builder.layout().chroot_root.join("firecracker");
+        assert_eq!(std::fs::read(staged).unwrap(), b"\x7fELF");
+    }
+
+    #[tokio::test]
+    async fn test_serial_console() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = TempDir::new("serial");
+        let serial_out = dir.join("serial.out");
+
+        // `cat` stands in for Firecracker echoing serial input to output.
+        let child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(std::fs::File::create(&serial_out).unwrap())
+            .spawn()
+            .unwrap();
+        let mut process = test_process(child);
+        process.current_dir = Some(dir.path().to_path_buf());
+        assert!(matches!(
+            process.serial_reader().await,
+            Err(Error::MissingConfig("serial_out_path"))
+        ));
+        process.serial_path = Some(process.host_path(Path::new("serial.out")));
+        assert_eq!(process.serial_path(), Some(serial_out.as_path()));
+
+        let mut input = process.serial_writer().unwrap();
+        assert!(process.serial_writer().is_err());
+        input.write_all(b"login: ").await.unwrap();
+        drop(input);
+        process.wait().await.unwrap();
+
+        let mut output = String::new();
+        let mut reader = process.serial_reader().await.unwrap();
+        reader.read_to_string(&mut output).await.unwrap();
+        assert_eq!(output, "login: ");
+    }
+
+    #[tokio::test]
+    async fn test_wait_timeout() {
+        let child = Command::new("sleep").arg("0.3").spawn().unwrap();
+        let mut process = test_process(child);
+
+        let status = process.wait_timeout(Duration::from_millis(10)).await;
+        assert!(status.unwrap().is_none());
+        assert!(process.pid().is_some());
+
+        let status = process.wait_timeout(Duration::from_secs(5)).await;
+        assert!(status.unwrap().unwrap().success());
+        assert!(process.pid().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_guard() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let process = |command: &str| {
+            let child = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            test_process(child)
+        };
+        let status = process("sleep 30").guard().shutdown().await.unwrap();
+        assert_eq!(status.unwrap().signal(), Some(libc::SIGTERM));
+
+        // Ignores SIGTERM, so the guard has to escalate. Wait for the
+        // trap to be installed before signalling.
+        let mut guard = process("trap '' TERM; echo ready; sleep 30").guard();
+        let stdout = guard.child.as_mut().unwrap().stdout.take().unwrap();
+        let mut line = String::new();
+        tokio::io::AsyncBufReadExt::read_line(&mut tokio::io::BufReader::new(stdout), &mut line)
+            .await
+            .unwrap();
+        let status = guard
+            .timeout(Duration::from_millis(100))
+            .shutdown()
+            .await
+            .unwrap();
+        assert_eq!(status.unwrap().signal(), Some(libc::SIGKILL));
+    }
+
+    #[test]
+    fn test_extra_args_come_last() {
+        let args = FirecrackerProcessBuilder::new("firecracker", "/tmp/fc.sock")
+            .extra_arg("--metadata")
+            .extra_args(["/tmp/metadata.json"])
+            .boot_timer(true)
+            .build_args();
+        assert_eq!(
+            args,
+            [
+                "--api-sock",
+                "/tmp/fc.sock",
+                "--boot-timer",
+                "--metadata",
+                "/tmp/metadata.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jailer_validation() {
+        let exec_file = std::env::current_exe().unwrap();
+        let builder = |uid| JailerProcessBuilder::new("jailer", &exec_file, "vm", uid, 1000);
+
+        assert!(builder(1000).validate().is_ok());
+        assert!(matches!(
+            builder(u32::MAX).validate(),
+            Err(Error::InvalidConfig(_))
+        ));
+        match builder(1000)
+            .netns("/var/run/netns/fc-sdk-missing")
+            .validate()
+        {
+            Err(Error::InvalidConfig(msg)) => {
+                assert!(msg.contains("/var/run/netns/fc-sdk-missing"))
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(
+            JailerProcessBuilder::new("jailer", "/nonexistent/firecracker", "vm", 1000, 1000)
+                .validate()
+                .is_err()
+        );
+        assert!(matches!(
+            builder(1000)
+                .socket_poll_interval(Duration::ZERO)
+                .validate(),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_api_ready_times_out_without_http() {
+        // Accepts connections (into the backlog) but never answers.
+        let path = unique_socket_path();
+        let _listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let result = wait_for_api_ready(
+            &path,
+            Duration::from_millis(100),
+            Duration::from_millis(10),
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::SocketTimeout { .. })));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_wait_for_api_ready() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        wait_for_api_ready(
+            mock.socket_path(),
+            Duration::from_secs(5),
+            Duration::from_millis(10),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(mock.calls(), ["GET /version"]);
+    }
+
+    #[tokio::test]
+    async fn test_stdio_redirect() {
+        let dir = TempDir::new("stderr");
+        let path = dir.join("stderr");
+        let stderr = std::fs::File::create(&path).unwrap();
+        // `sh` rejects the `--api-sock` option and exits with a message.
+        let result = FirecrackerProcessBuilder::new_auto("sh")
+            .null_stdio()
+            .stderr(stderr)
+            .spawn()
+            .await;
+        assert!(matches!(result, Err(Error::ProcessExited(Some(_)))));
+        assert!(!std::fs::read_to_string(&path).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_zero_poll_interval() {
+        let result = FirecrackerProcessBuilder::new_auto("/nonexistent/firecracker")
+            .socket_poll_interval(Duration::ZERO)
+            .spawn()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidConfig(_))));
+
+        // Clamped rather than spinning; the wait still times out.
+        let path = unique_socket_path();
+        let result = wait_for_socket(&path, Duration::from_millis(20), Duration::ZERO, None).await;
+        assert!(matches!(result, Err(Error::SocketTimeout { .. })));
+    }
 }
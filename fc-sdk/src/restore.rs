@@ -0,0 +1,326 @@
+//! Snapshot restore flow.
+//!
+//! [`RestoreBuilder`] wraps a [`SnapshotLoadParams`] and performs local sanity
+//! checks before asking Firecracker to load the snapshot. The standalone
+//! [`restore()`](crate::restore) and [`restore_with_client()`](crate::restore_with_client)
+//! functions are thin wrappers around it.
+
+use std::fs::File;
+use std::path::Path;
+
+use fc_api::Client;
+use fc_api::types::{MemoryBackend, MemoryBackendBackendType, NetworkOverride, SnapshotLoadParams};
+
+use crate::error::{Error, Result};
+use crate::snapshot::SnapshotInfo;
+use crate::trace::traced;
+use crate::version::snapshot_compatible;
+use crate::vm::Vm;
+
+/// Builder for restoring a microVM from a snapshot.
+///
+/// Must be used against a fresh Firecracker process (before configuring any
+/// resources other than logger and metrics).
+///
+/// # Example
+///
+/// ```no_run
+/// use fc_sdk::{RestoreBuilder, types::*};
+///
+/// # async fn example() -> fc_sdk::Result<()> {
+/// let vm = RestoreBuilder::new(
+///     "/tmp/firecracker.sock",
+///     SnapshotLoadParams {
+///         snapshot_path: "/path/to/snapshot".into(),
+///         mem_file_path: Some("/path/to/mem".into()),
+///         mem_backend: None,
+///         enable_diff_snapshots: None,
+///         track_dirty_pages: None,
+///         resume_vm: Some(true),
+///         network_overrides: vec![],
+///     },
+/// )
+/// .load()
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RestoreBuilder {
+    client: Client,
+    params: SnapshotLoadParams,
+    local_checks: bool,
+    verify_version_compat: bool,
+}
+
+impl RestoreBuilder {
+    /// Create a new restore builder connected to the Firecracker socket at `socket_path`.
+    pub fn new(socket_path: impl AsRef<Path>, params: SnapshotLoadParams) -> Self {
+        Self::with_client(crate::connection::connect(socket_path), params)
+    }
+
+    /// Create a new restore builder using an existing API client.
+    pub fn with_client(client: Client, params: SnapshotLoadParams) -> Self {
+        Self {
+            client,
+            params,
+            local_checks: true,
+            verify_version_compat: false,
+        }
+    }
+
+    /// Serve guest memory through a userfaultfd (UFFD) page-fault handler.
+    ///
+    /// Sets `mem_backend` to a `Uffd` backend at `socket_path` and clears
+    /// `mem_file_path`. The handler must already be listening on `socket_path`
+    /// when [`load()`](Self::load) is called: Firecracker connects to it during
+    /// the load request to hand over the memory regions and the userfaultfd,
+    /// and the guest starts faulting pages as soon as it is resumed.
+    pub fn uffd(mut self, socket_path: impl AsRef<Path>) -> Self {
+        self.params.mem_file_path = None;
+        self.params.mem_backend = Some(MemoryBackend {
+            backend_type: MemoryBackendBackendType::Uffd,
+            backend_path: socket_path.as_ref().display().to_string(),
+        });
+        self
+    }
+
+    /// Attach the snapshot's network interface `iface_id` to the host TAP
+    /// device `host_dev_name` instead of the one recorded in the snapshot.
+    ///
+    /// Each interface may be overridden at most once.
+    pub fn network_override(
+        mut self,
+        iface_id: impl Into<String>,
+        host_dev_name: impl Into<String>,
+    ) -> Self {
+        self.params.network_overrides.push(NetworkOverride {
+            iface_id: iface_id.into(),
+            host_dev_name: host_dev_name.into(),
+        });
+        self
+    }
+
+    /// Skip the local existence checks on the snapshot and memory files.
+    ///
+    /// Use this when the paths are only meaningful to the Firecracker process
+    /// (e.g. inside a jailer chroot) rather than to the caller.
+    pub fn skip_local_checks(mut self) -> Self {
+        self.local_checks = false;
+        self
+    }
+
+    /// Check that the Firecracker process can load the snapshot before
+    /// sending the load request.
+    ///
+    /// The snapshot file is [inspected](crate::snapshot::inspect) locally and
+    /// its architecture and data format version are compared against the
+    /// host and the process's Firecracker version, following Firecracker's
+    /// snapshot compatibility rules. Disabled by default.
+    pub fn verify_version_compat(mut self, verify: bool) -> Self {
+        self.verify_version_compat = verify;
+        self
+    }
+
+    /// Load the snapshot and return a [`Vm`] handle.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidConfig`] unless exactly one of `mem_file_path`
+    /// and `mem_backend` is set or if an interface is overridden twice,
+    /// [`Error::Io`] naming the offending path if the snapshot or memory file
+    /// cannot be opened locally, [`Error::Other`] if
+    /// [version verification](Self::verify_version_compat) fails, or an API
+    /// error if Firecracker rejects the load.
+    pub async fn load(self) -> Result<Vm> {
+        validate_mem_source(&self.params)?;
+        validate_network_overrides(&self.params)?;
+        if self.local_checks {
+            check_local_files(&self.params)?;
+        }
+        if self.verify_version_compat {
+            let info = crate::snapshot::inspect(Path::new(&self.params.snapshot_path))?;
+            let version = traced(
+                "get_firecracker_version",
+                self.client.get_firecracker_version().send(),
+            )
+            .await?;
+            let version = crate::vm::firecracker_semver(&version.firecracker_version)?;
+            check_compat(&info, version, std::env::consts::ARCH)?;
+        }
+        traced(
+            "load_snapshot",
+            self.client.load_snapshot().body(self.params).send(),
+        )
+        .await?;
+        Ok(Vm::new(self.client))
+    }
+}
+
+/// Firecracker requires exactly one of `mem_file_path` and `mem_backend`.
+fn validate_mem_source(params: &SnapshotLoadParams) -> Result<()> {
+    match (&params.mem_file_path, &params.mem_backend) {
+        (Some(_), Some(_)) => Err(Error::InvalidConfig(
+            "mem_file_path and mem_backend are mutually exclusive".to_owned(),
+        )),
+        (None, None) => Err(Error::InvalidConfig(
+            "one of mem_file_path or mem_backend is required".to_owned(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_network_overrides(params: &SnapshotLoadParams) -> Result<()> {
+    for (i, network_override) in params.network_overrides.iter().enumerate() {
+        if params.network_overrides[..i]
+            .iter()
+            .any(|other| other.iface_id == network_override.iface_id)
+        {
+            return Err(Error::InvalidConfig(format!(
+                "duplicate network override for interface: {}",
+                network_override.iface_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Reject a snapshot that Firecracker `version` on `arch` cannot load.
+fn check_compat(info: &SnapshotInfo, version: (u64, u64, u64), arch: &str) -> Result<()> {
+    let (major, minor, patch) = version;
+    let (format_major, format_minor, format_patch) = info.format_version;
+    if info.arch != arch {
+        return Err(Error::Other(format!(
+            "snapshot was taken on {}, cannot restore on {arch}",
+            info.arch
+        )));
+    }
+    match snapshot_compatible(version, info.format_version) {
+        Some(true) => Ok(()),
+        Some(false) => Err(Error::Other(format!(
+            "snapshot format {format_major}.{format_minor}.{format_patch} cannot be \
+             restored by Firecracker {major}.{minor}.{patch}"
+        ))),
+        None => Err(Error::Other(format!(
+            "cannot verify snapshot compatibility: unknown snapshot format for \
+             Firecracker {major}.{minor}.{patch}"
+        ))),
+    }
+}
+
+/// Verify that the snapshot file (and file-backed guest memory, if any) can be opened.
+fn check_local_files(params: &SnapshotLoadParams) -> Result<()> {
+    check_readable("snapshot file", &params.snapshot_path)?;
+    if let Some(mem_file_path) = &params.mem_file_path {
+        check_readable("memory file", mem_file_path)?;
+    }
+    if let Some(backend) = &params.mem_backend
+        && backend.backend_type == MemoryBackendBackendType::File
+    {
+        check_readable("memory file", &backend.backend_path)?;
+    }
+    Ok(())
+}
+
+fn check_readable(what: &str, path: &str) -> Result<()> {
+    File::open(path)
+        .map(drop)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{what} {path}: {e}")).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    fn params(snapshot_path: &str, mem_file_path: Option<&str>) -> SnapshotLoadParams {
+        SnapshotLoadParams {
+            snapshot_path: snapshot_path.to_owned(),
+            mem_file_path: mem_file_path.map(str::to_owned),
+            mem_backend: None,
+            enable_diff_snapshots: None,
+            track_dirty_pages: None,
+            resume_vm: None,
+            network_overrides: vec![],
+        }
+    }
+
+    #[test]
+    fn test_local_checks_report_missing_paths() {
+        let missing = "/nonexistent/fc-sdk/snapshot";
+        match check_local_files(&params(missing, None)) {
+            Err(Error::Io(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+                assert!(e.to_string().contains(missing));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        let existing = std::env::current_exe().unwrap();
+        let existing = existing.to_str().unwrap();
+        let missing_mem = "/nonexistent/fc-sdk/mem";
+        match check_local_files(&params(existing, Some(missing_mem))) {
+            Err(Error::Io(e)) => assert!(e.to_string().contains(missing_mem)),
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        assert!(check_local_files(&params(existing, Some(existing))).is_ok());
+    }
+
+    #[test]
+    fn test_uffd_replaces_mem_file_path() {
+        let builder = RestoreBuilder::new(
+            "/tmp/fc.sock",
+            params("/path/to/snapshot", Some("/path/to/mem")),
+        )
+        .uffd("/tmp/uffd.sock");
+
+        assert!(builder.params.mem_file_path.is_none());
+        let backend = builder.params.mem_backend.as_ref().unwrap();
+        assert_eq!(backend.backend_type, MemoryBackendBackendType::Uffd);
+        assert_eq!(backend.backend_path, "/tmp/uffd.sock");
+        assert!(validate_mem_source(&builder.params).is_ok());
+        // Neither memory source is set.
+        assert!(validate_mem_source(&params("/path/to/snapshot", None)).is_err());
+
+        let mut both = params("/path/to/snapshot", Some("/path/to/mem"));
+        both.mem_backend = builder.params.mem_backend.clone();
+        match validate_mem_source(&both) {
+            Err(Error::InvalidConfig(_)) => {}
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_check_compat() {
+        let info = SnapshotInfo {
+            arch: "x86_64",
+            format_version: (6, 0, 0),
+            firecracker_series: Some((1, 12)),
+            mem_size_mib: 512,
+            vcpu_count: None,
+        };
+        assert!(check_compat(&info, (1, 12, 1), "x86_64").is_ok());
+        assert!(check_compat(&info, (1, 12, 1), "aarch64").is_err());
+
+        let err = check_compat(&info, (1, 10, 0), "x86_64").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "snapshot format 6.0.0 cannot be restored by Firecracker 1.10.0"
+        );
+        assert!(check_compat(&info, (1, 6, 0), "x86_64").is_err());
+    }
+
+    #[test]
+    fn test_network_overrides_must_be_unique() {
+        let builder = RestoreBuilder::new("/tmp/test.sock", params("/snap", Some("/mem")))
+            .network_override("eth0", "tap0")
+            .network_override("eth1", "tap1");
+        assert!(validate_network_overrides(&builder.params).is_ok());
+
+        let builder = builder.network_override("eth0", "tap2");
+        assert!(matches!(
+            validate_network_overrides(&builder.params),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+//! Offline inspection of Firecracker snapshot files.
+//!
+//! A snapshot file (the `snapshot_path` of a snapshot, not the memory file)
+//! starts with a small header identifying the host architecture and the
+//! snapshot data format version, followed by the serialized microVM state.
+//! [`inspect()`] reads just enough of it to check compatibility before a
+//! restore is attempted.
+//!
+//! Only the format introduced in Firecracker 1.7 (bincode with fixed-width
+//! little-endian integers) is understood; older versionize-based snapshots
+//! are rejected.
+//!
+//! The vCPU states follow architecture-specific VM state whose layout changes
+//! between releases. On x86_64 that state ends with the serialized KVM PIT,
+//! clock and interrupt controller structures, whose sizes are fixed by the
+//! kernel ABI, so the vCPU count is found right after them.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::version::{parse_version, snapshot_format_series};
+
+/// Snapshot magic for x86_64 hosts.
+const MAGIC_X86_64: u64 = 0x0710_1984_8664_0000;
+/// Snapshot magic for aarch64 hosts.
+const MAGIC_AARCH64: u64 = 0x0710_1984_AAAA_0000;
+/// Upper bound for the header's version string, to reject garbage early.
+const MAX_VERSION_LEN: u64 = 64;
+/// Sizes of the KVM structures ending the x86_64 VM state, each serialized
+/// with a length prefix: `kvm_pit_state2`, `kvm_clock_data` and three
+/// `kvm_irqchip`s.
+const X86_64_VM_STATE_TAIL: [u64; 5] = [112, 48, 520, 520, 520];
+/// How much of the microVM state to search for the vCPU states.
+const VCPU_SCAN_LIMIT: u64 = 1 << 20;
+/// Most vCPUs a Firecracker microVM can have.
+const MAX_VCPUS: u64 = 32;
+
+/// Metadata read from a snapshot file by [`inspect()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SnapshotInfo {
+    /// Host architecture the snapshot was taken on, `"x86_64"` or `"aarch64"`.
+    pub arch: &'static str,
+    /// Snapshot data format version as `(major, minor, patch)`.
+    ///
+    /// This is the version of the state encoding, not the Firecracker
+    /// release; each release documents the format version it reads and
+    /// writes.
+    pub format_version: (u64, u64, u64),
+    /// Firecracker release series, as `(major, minor)`, that writes this
+    /// data format, or `None` if the format is not known to this crate.
+    pub firecracker_series: Option<(u64, u64)>,
+    /// Guest memory size in MiB.
+    pub mem_size_mib: u64,
+    /// Number of vCPUs, or `None` if the vCPU states could not be located
+    /// (always on aarch64).
+    pub vcpu_count: Option<u64>,
+}
+
+/// Read the header and microVM state of the snapshot file at `path`.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if the file cannot be read and [`Error::Other`] if
+/// it is not a snapshot in a supported format.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn example() -> fc_sdk::Result<()> {
+/// let info = fc_sdk::snapshot::inspect("/path/to/snapshot".as_ref())?;
+/// println!("{} snapshot, {} MiB", info.arch, info.mem_size_mib);
+/// if let Some(vcpus) = info.vcpu_count {
+///     println!("{vcpus} vCPUs");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect(path: &Path) -> Result<SnapshotInfo> {
+    let mut reader = BufReader::new(File::open(path)?);
+    parse(&mut reader).map_err(|e| match e {
+        Error::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Error::Other(format!("truncated snapshot file: {}", path.display()))
+        }
+        Error::Other(msg) => Error::Other(format!("{msg}: {}", path.display())),
+        e => e,
+    })
+}
+
+fn parse(reader: &mut impl Read) -> Result<SnapshotInfo> {
+    let magic = read_u64(reader)?;
+    let arch = match magic {
+        MAGIC_X86_64 => "x86_64",
+        MAGIC_AARCH64 => "aarch64",
+        // The versionize format kept its data version in the low 16 bits.
+        _ if magic & !0xffff == MAGIC_X86_64 || magic & !0xffff == MAGIC_AARCH64 => {
+            return Err(Error::Other(
+                "snapshot uses the pre-1.7 versionize format".to_owned(),
+            ));
+        }
+        _ => return Err(Error::Other("not a Firecracker snapshot".to_owned())),
+    };
+
+    let len = read_u64(reader)?;
+    if len > MAX_VERSION_LEN {
+        return Err(Error::Other("invalid snapshot header".to_owned()));
+    }
+    let mut version = vec![0; len as usize];
+    reader.read_exact(&mut version)?;
+    let format_version = std::str::from_utf8(&version)
+        .ok()
+        .and_then(parse_version)
+        .ok_or_else(|| Error::Other("invalid snapshot format version".to_owned()))?;
+
+    // `VmInfo` leads the microVM state, starting with the memory size.
+    let mem_size_mib = read_u64(reader)?;
+
+    let vcpu_count = if arch == "x86_64" {
+        let mut state = Vec::new();
+        reader.take(VCPU_SCAN_LIMIT).read_to_end(&mut state)?;
+        find_vcpu_count(&state)
+    } else {
+        None
+    };
+
+    Ok(SnapshotInfo {
+        arch,
+        format_version,
+        firecracker_series: snapshot_format_series(format_version),
+        mem_size_mib,
+        vcpu_count,
+    })
+}
+
+/// Length of the `Vec<VcpuState>` that follows the x86_64 VM state in
+/// `state`, if found and plausible.
+fn find_vcpu_count(state: &[u8]) -> Option<u64> {
+    let u64_at = |pos: usize| {
+        let bytes = state.get(pos..pos.checked_add(8)?)?;
+        Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    (0..state.len()).find_map(|start| {
+        let mut pos = start;
+        for size in X86_64_VM_STATE_TAIL {
+            if u64_at(pos)? != size {
+                return None;
+            }
+            pos += 8 + size as usize;
+        }
+        let count = u64_at(pos)?;
+        (1..=MAX_VCPUS).contains(&count).then_some(count)
+    })
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn header(magic: u64, version: &str, mem_size_mib: u64) -> Vec<u8> {
+        let mut data = magic.to_le_bytes().to_vec();
+        data.extend((version.len() as u64).to_le_bytes());
+        data.extend(version.as_bytes());
+        data.extend(mem_size_mib.to_le_bytes());
+        data.extend([0; 32]);
+        data
+    }
+
+    /// The x86_64 VM state tail followed by `vcpus` vCPU states.
+    fn vm_state(vcpus: u64) -> Vec<u8> {
+        let mut data = Vec::new();
+        for size in X86_64_VM_STATE_TAIL {
+            data.extend(size.to_le_bytes());
+            data.extend(vec![0x5a; size as usize]);
+        }
+        data.extend(vcpus.to_le_bytes());
+        data.extend([0; 64]);
+        data
+    }
+
+    #[test]
+    fn test_parse_snapshot_header() {
+        let info = parse(&mut header(MAGIC_X86_64, "6.0.0", 512).as_slice()).unwrap();
+        assert_eq!(
+            info,
+            SnapshotInfo {
+                arch: "x86_64",
+                format_version: (6, 0, 0),
+                firecracker_series: Some((1, 12)),
+                mem_size_mib: 512,
+                vcpu_count: None,
+            }
+        );
+        let info = parse(&mut header(MAGIC_AARCH64, "4.0.0", 128).as_slice()).unwrap();
+        assert_eq!(info.arch, "aarch64");
+        assert_eq!(info.firecracker_series, Some((1, 10)));
+        let info = parse(&mut header(MAGIC_X86_64, "9.1.0", 128).as_slice()).unwrap();
+        assert_eq!(info.firecracker_series, None);
+
+        assert!(parse(&mut header(MAGIC_X86_64 | 3, "6.0.0", 512).as_slice()).is_err());
+        assert!(parse(&mut header(0xdead_beef, "6.0.0", 512).as_slice()).is_err());
+        assert!(parse(&mut header(MAGIC_X86_64, "six", 512).as_slice()).is_err());
+        assert!(parse(&mut &header(MAGIC_X86_64, "6.0.0", 512)[..20]).is_err());
+    }
+
+    #[test]
+    fn test_parse_vcpu_count() {
+        let snapshot = |magic, vcpus| {
+            let mut data = header(magic, "6.0.0", 512);
+            data.extend(vm_state(vcpus));
+            data
+        };
+        let info = parse(&mut snapshot(MAGIC_X86_64, 2).as_slice()).unwrap();
+        assert_eq!(info.vcpu_count, Some(2));
+
+        // Implausible counts and other architectures are not reported.
+        assert_eq!(
+            parse(&mut snapshot(MAGIC_X86_64, 0).as_slice())
+                .unwrap()
+                .vcpu_count,
+            None
+        );
+        assert_eq!(
+            parse(&mut snapshot(MAGIC_X86_64, MAX_VCPUS + 1).as_slice())
+                .unwrap()
+                .vcpu_count,
+            None
+        );
+        assert_eq!(
+            parse(&mut snapshot(MAGIC_AARCH64, 2).as_slice())
+                .unwrap()
+                .vcpu_count,
+            None
+        );
+    }
+
+    #[test]
+    fn test_inspect_names_path() {
+        let dir = TempDir::new("snapshot");
+        let path = dir.join("vm.snap");
+        std::fs::write(&path, &header(MAGIC_X86_64, "6.0.0", 256)[..12]).unwrap();
+        let err = inspect(&path).unwrap_err();
+        assert!(err.to_string().contains("truncated snapshot file"));
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
+}
@@ -0,0 +1,61 @@
+//! Fixtures shared by the unit tests of several modules.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A scratch directory under [`std::env::temp_dir()`], removed together with
+/// its contents on drop, so a failing test does not leave it behind.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create an empty directory whose name starts with `fc-sdk-{prefix}`.
+    ///
+    /// Format: `{std::env::temp_dir()}/fc-sdk-{prefix}-{pid}-{nanos}-{seq}`.
+    pub(crate) fn new(prefix: &str) -> Self {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path = std::env::temp_dir().join(format!(
+            "fc-sdk-{prefix}-{}-{nanos}-{seq}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_dir_is_unique_and_removed_on_drop() {
+        let a = TempDir::new("test-util");
+        let b = TempDir::new("test-util");
+        assert_ne!(a.path(), b.path());
+
+        std::fs::write(a.join("file"), b"data").unwrap();
+        let path = a.path().to_path_buf();
+        drop(a);
+        assert!(!path.exists());
+        assert!(b.path().is_dir());
+    }
+}
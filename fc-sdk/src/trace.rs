@@ -0,0 +1,175 @@
+//! Optional `tracing` instrumentation around Firecracker API calls.
+//!
+//! With the `tracing` feature disabled, [`traced`] and [`traced_id`] simply
+//! await the request.
+
+use std::future::Future;
+
+/// Await an API request inside a `debug` span named after its endpoint.
+pub(crate) async fn traced<T, E>(
+    endpoint: &'static str,
+    request: impl Future<Output = Result<T, fc_api::Error<E>>>,
+) -> Result<T, fc_api::Error<E>> {
+    traced_id(endpoint, "", request).await
+}
+
+/// Like [`traced`], additionally recording the device or resource id.
+#[cfg(feature = "tracing")]
+pub(crate) async fn traced_id<T, E>(
+    endpoint: &'static str,
+    id: &str,
+    request: impl Future<Output = Result<T, fc_api::Error<E>>>,
+) -> Result<T, fc_api::Error<E>> {
+    use tracing::Instrument;
+
+    let span = if id.is_empty() {
+        tracing::debug_span!("fc_api", endpoint)
+    } else {
+        tracing::debug_span!("fc_api", endpoint, id)
+    };
+    let result = request.instrument(span.clone()).await;
+    span.in_scope(|| match &result {
+        Ok(_) => tracing::debug!("request succeeded"),
+        Err(e) => tracing::debug!(status = ?e.status(), "request failed"),
+    });
+    result
+}
+
+/// Like [`traced`], additionally recording the device or resource id.
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn traced_id<T, E>(
+    _endpoint: &'static str,
+    _id: &str,
+    request: impl Future<Output = Result<T, fc_api::Error<E>>>,
+) -> Result<T, fc_api::Error<E>> {
+    request.await
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+
+    /// Fields of a span or event, keyed by name.
+    type Fields = std::collections::HashMap<String, String>;
+
+    /// Records the fields of this crate's spans, and of its events with the
+    /// span they were emitted in.
+    #[derive(Default)]
+    struct Recorder {
+        spans: std::sync::Mutex<Vec<Fields>>,
+        events: std::sync::Mutex<Vec<(Option<u64>, Fields)>>,
+        current: std::sync::Mutex<Vec<u64>>,
+    }
+
+    impl Recorder {
+        fn install() -> (std::sync::Arc<Self>, tracing::subscriber::DefaultGuard) {
+            let recorder = std::sync::Arc::new(Self::default());
+            let guard = tracing::subscriber::set_default(recorder.clone());
+            (recorder, guard)
+        }
+
+        /// The fields of the only span, and of the events emitted in it.
+        fn api_span(&self) -> (Fields, Vec<Fields>) {
+            let spans = self.spans.lock().unwrap();
+            assert_eq!(spans.len(), 1, "expected one span: {spans:?}");
+            let events = self.events.lock().unwrap();
+            let events = events
+                .iter()
+                .filter(|(span, _)| *span == Some(1))
+                .map(|(_, fields)| fields.clone())
+                .collect();
+            (spans[0].clone(), events)
+        }
+    }
+
+    struct Visitor<'a>(&'a mut Fields);
+
+    impl tracing::field::Visit for Visitor<'_> {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name().into(), value.into());
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().into(), format!("{value:?}"));
+        }
+    }
+
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, metadata: &tracing::Metadata<'_>) -> bool {
+            metadata.target().starts_with("fc_sdk")
+        }
+
+        fn new_span(&self, attrs: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut fields = Fields::new();
+            attrs.record(&mut Visitor(&mut fields));
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(fields);
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            let mut spans = self.spans.lock().unwrap();
+            values.record(&mut Visitor(&mut spans[span.into_u64() as usize - 1]));
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = Fields::new();
+            event.record(&mut Visitor(&mut fields));
+            let span = self.current.lock().unwrap().last().copied();
+            self.events.lock().unwrap().push((span, fields));
+        }
+
+        fn enter(&self, span: &tracing::span::Id) {
+            self.current.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _: &tracing::span::Id) {
+            self.current.lock().unwrap().pop();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_traced_records_endpoint_and_error_status() {
+        let (recorder, _guard) = Recorder::install();
+        let error = fc_api::Error::ErrorResponse(fc_api::ResponseValue::new(
+            (),
+            reqwest::StatusCode::BAD_REQUEST,
+            reqwest::header::HeaderMap::new(),
+        ));
+        let result = traced("put_guest_boot_source", async { Err::<(), _>(error) }).await;
+        assert!(result.is_err());
+
+        let (span, events) = recorder.api_span();
+        assert_eq!(span["endpoint"], "put_guest_boot_source");
+        assert!(!span.contains_key("id"));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["message"], "request failed");
+        assert_eq!(events[0]["status"], "Some(400)");
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_vm_update_spans_record_device_id() {
+        use fc_api::types::{RateLimiter, TokenBucket};
+
+        let mock = crate::connection::MockClient::new().unwrap();
+        let (recorder, _guard) = Recorder::install();
+        let limiter = RateLimiter {
+            bandwidth: Some(TokenBucket {
+                size: 1024,
+                one_time_burst: None,
+                refill_time: 100,
+            }),
+            ops: None,
+        };
+        mock.vm().throttle_rx("eth0", limiter).await.unwrap();
+
+        let (span, events) = recorder.api_span();
+        assert_eq!(span["endpoint"], "patch_guest_network_interface_by_id");
+        assert_eq!(span["id"], "eth0");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["message"], "request succeeded");
+    }
+}
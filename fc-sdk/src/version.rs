@@ -0,0 +1,153 @@
+//! Firecracker version parsing and capability detection.
+
+/// Parse a strict `X.Y.Z` version into its numeric components.
+///
+/// No prefix or suffix is accepted; callers strip a leading `v` or a
+/// `-dev`-style suffix themselves where the format allows it.
+///
+/// ```
+/// use fc_sdk::version::parse_version;
+///
+/// assert_eq!(parse_version("1.12.1"), Some((1, 12, 1)));
+/// assert_eq!(parse_version("v1.12.1"), None);
+/// ```
+pub fn parse_version(value: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = value.split('.').map(|part| {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        part.parse().ok()
+    });
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(major), Some(minor), Some(patch), None) => Some((major?, minor?, patch?)),
+        _ => None,
+    }
+}
+
+/// Optional Firecracker capability, gated on the release that introduced it.
+///
+/// Check it against a running instance with
+/// [`Vm::supports()`](crate::Vm::supports).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// virtio-pmem devices (`/pmem`).
+    Pmem,
+    /// virtio-mem memory hotplug (`/hotplug/memory`).
+    MemoryHotplug,
+    /// Balloon free page hinting and reporting.
+    BalloonHinting,
+    /// PCI transport for virtio devices (`--enable-pci`).
+    Pci,
+    /// Diff snapshots of a microVM booted without `track_dirty_pages`.
+    ///
+    /// Older releases can only take diff snapshots with dirty page tracking
+    /// enabled.
+    DiffSnapshotWithoutTracking,
+}
+
+impl Feature {
+    /// First Firecracker release that supports this feature.
+    pub const fn min_version(self) -> (u64, u64, u64) {
+        match self {
+            Self::Pci | Self::DiffSnapshotWithoutTracking => (1, 13, 0),
+            Self::Pmem | Self::MemoryHotplug | Self::BalloonHinting => (1, 14, 0),
+        }
+    }
+
+    /// Whether Firecracker `version` supports this feature.
+    pub fn supported_by(self, version: (u64, u64, u64)) -> bool {
+        version >= self.min_version()
+    }
+}
+
+/// A `(major, minor, patch)` version.
+type Version = (u64, u64, u64);
+
+/// Snapshot data format version written by each Firecracker release series
+/// since the format was introduced in 1.7.
+///
+/// Keep in sync with Firecracker's `docs/snapshotting/versioning.md`.
+const SNAPSHOT_FORMAT_VERSIONS: &[((u64, u64), Version)] = &[
+    ((1, 7), (1, 0, 0)),
+    ((1, 8), (2, 0, 0)),
+    ((1, 9), (3, 0, 0)),
+    ((1, 10), (4, 0, 0)),
+    ((1, 11), (5, 0, 0)),
+    ((1, 12), (6, 0, 0)),
+    ((1, 13), (7, 0, 0)),
+    ((1, 14), (8, 0, 0)),
+];
+
+/// Snapshot data format version read and written by Firecracker `version`,
+/// if known.
+///
+/// Patch releases share the format of their minor series.
+pub fn snapshot_format_version(version: Version) -> Option<Version> {
+    let (major, minor, _) = version;
+    SNAPSHOT_FORMAT_VERSIONS
+        .iter()
+        .find(|(series, _)| *series == (major, minor))
+        .map(|&(_, format)| format)
+}
+
+/// Firecracker release series, as `(major, minor)`, that writes snapshot data
+/// format `format`, if known.
+pub fn snapshot_format_series(format: Version) -> Option<(u64, u64)> {
+    SNAPSHOT_FORMAT_VERSIONS
+        .iter()
+        .find(|(_, written)| *written == format)
+        .map(|&(series, _)| series)
+}
+
+/// Whether Firecracker `version` can load a snapshot in data format `format`,
+/// or `None` if the release is not known.
+///
+/// Firecracker loads a snapshot when the format has the same major version
+/// as its own and a minor version no newer than its own.
+///
+/// ```
+/// use fc_sdk::version::snapshot_compatible;
+///
+/// assert_eq!(snapshot_compatible((1, 12, 1), (6, 0, 0)), Some(true));
+/// assert_eq!(snapshot_compatible((1, 12, 1), (4, 0, 0)), Some(false));
+/// ```
+pub fn snapshot_compatible(version: Version, format: Version) -> Option<bool> {
+    let (major, minor, _) = snapshot_format_version(version)?;
+    Some(format.0 == major && format.1 <= minor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.25.10"), Some((0, 25, 10)));
+        assert_eq!(parse_version("1.12"), None);
+        assert_eq!(parse_version("1.12.1.0"), None);
+        assert_eq!(parse_version("1..1"), None);
+        assert_eq!(parse_version("1.12.1-dev"), None);
+        assert_eq!(parse_version("1.+2.1"), None);
+    }
+
+    #[test]
+    fn test_feature_supported_by() {
+        assert!(Feature::Pci.supported_by((1, 13, 0)));
+        assert!(Feature::Pci.supported_by((2, 0, 0)));
+        assert!(!Feature::Pci.supported_by((1, 12, 1)));
+        assert!(!Feature::Pmem.supported_by((1, 13, 5)));
+    }
+
+    #[test]
+    fn test_snapshot_compatible() {
+        assert_eq!(snapshot_format_version((1, 10, 1)), Some((4, 0, 0)));
+        assert_eq!(snapshot_format_version((1, 6, 0)), None);
+        assert_eq!(snapshot_format_series((4, 0, 0)), Some((1, 10)));
+        assert_eq!(snapshot_format_series((4, 1, 0)), None);
+        assert_eq!(snapshot_compatible((1, 12, 0), (6, 0, 0)), Some(true));
+        assert_eq!(snapshot_compatible((1, 12, 0), (6, 1, 0)), Some(false));
+        assert_eq!(snapshot_compatible((1, 12, 0), (5, 0, 0)), Some(false));
+        assert_eq!(snapshot_compatible((9, 0, 0), (6, 0, 0)), None);
+    }
+}
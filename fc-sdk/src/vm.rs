@@ -1,27 +1,99 @@
-use std::path::Path;
+use std::future::Future;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use fc_api::Client;
 use fc_api::types::{
     Balloon, BalloonHintingStatus, BalloonStartCmd, BalloonStats, BalloonStatsUpdate,
-    BalloonUpdate, FirecrackerVersion, FullVmConfiguration, InstanceActionInfoActionType,
-    InstanceInfo, MachineConfiguration, MemoryHotplugSizeUpdate, MemoryHotplugStatus, PartialDrive,
-    PartialNetworkInterface, SnapshotCreateParams, SnapshotCreateParamsSnapshotType,
-    SnapshotLoadParams, VmState,
+    BalloonUpdate, CpuTemplate, Drive, FirecrackerVersion, FullVmConfiguration,
+    InstanceActionInfoActionType, InstanceInfo, InstanceInfoState, MachineConfiguration,
+    MachineConfigurationHugePages, MemoryHotplugSizeUpdate, MemoryHotplugStatus, MmdsConfigVersion,
+    NetworkInterface, PartialDrive, PartialNetworkInterface, Pmem, RateLimiter,
+    SnapshotCreateParams, SnapshotCreateParamsSnapshotType, SnapshotLoadParams, VmState,
 };
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
 
-use crate::connection::connect;
-use crate::error::Result;
+use crate::builder::RetryPolicy;
+use crate::error::{Error, Result};
+use crate::process::{FirecrackerProcessBuilder, RunningVm};
+use crate::restore::RestoreBuilder;
+use crate::trace::{traced, traced_id};
+use crate::version::{Feature, parse_version};
+
+/// How far `actual_mib` may be from the target for the balloon to count as settled.
+const BALLOON_TOLERANCE_MIB: i64 = 1;
+
+/// Interval between balloon statistics polls while waiting for the target.
+const BALLOON_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Interval between instance state polls while waiting for a state change.
+const STATE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Header carrying an MMDS V2 session token on guest reads.
+pub const MMDS_TOKEN_HEADER: &str = "X-metadata-token";
+
+/// Header with the requested lifetime, in seconds, of an MMDS V2 session token.
+pub const MMDS_TOKEN_TTL_HEADER: &str = "X-metadata-token-ttl-seconds";
+
+/// Longest MMDS session token lifetime Firecracker accepts, in seconds.
+const MMDS_TOKEN_MAX_TTL_SECONDS: u32 = 21600;
 
 /// Handle to a running Firecracker microVM.
 ///
-/// Obtained from [`VmBuilder::start()`](crate::VmBuilder::start) or [`restore()`].
+/// Obtained from [`VmBuilder::start()`](crate::VmBuilder::start), [`restore()`], or
+/// [`Vm::from_client()`] for an instance managed elsewhere.
+/// [`VmBuilder::configure()`](crate::VmBuilder::configure) returns a handle to a
+/// configured but not yet started microVM; call [`start()`](Self::start) to boot it.
 pub struct Vm {
     client: Client,
+    retry: RetryPolicy,
 }
 
 impl Vm {
     pub(crate) fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            retry: RetryPolicy::NONE,
+        }
+    }
+
+    /// Retry requests that fail to reach the API according to `policy`.
+    ///
+    /// Applies to the idempotent requests made through this handle (`GET`,
+    /// `PUT` and `PATCH`) when the error is [retryable](Error::is_retryable),
+    /// e.g. the connection was refused or reset. Instance actions such as
+    /// InstanceStart and snapshot creation are always sent exactly once,
+    /// since a request that was reset may already have taken effect. By
+    /// default nothing is retried.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Wrap an existing API client connected to an already-started microVM.
+    ///
+    /// No request is sent; the caller is responsible for ensuring the
+    /// instance behind `client` has been started or restored.
+    pub fn from_client(client: Client) -> Self {
+        Self::new(client)
+    }
+
+    /// Attach to an already-running microVM through its API socket.
+    ///
+    /// Sends `GET /` to confirm the instance is alive before returning, e.g.
+    /// for a microVM started with `fc-cli start --detach`.
+    pub async fn attach(socket_path: impl AsRef<Path>) -> Result<Self> {
+        let client = crate::connection::try_connect(socket_path)?;
+        Self::attach_with_client(client).await
+    }
+
+    /// Like [`attach()`](Self::attach), using an existing API client.
+    pub async fn attach_with_client(client: Client) -> Result<Self> {
+        let vm = Self::new(client);
+        vm.describe().await?;
+        Ok(vm)
     }
 
     // =========================================================================
@@ -30,60 +102,350 @@ impl Vm {
 
     /// Get general information about the instance.
     pub async fn describe(&self) -> Result<InstanceInfo> {
-        let info = self.client.describe_instance().send().await?;
+        let info = self
+            .retry
+            .run(|| traced("describe_instance", self.client.describe_instance().send()))
+            .await?;
         Ok(info.into_inner())
     }
 
+    /// Like [`describe()`](Self::describe), failing with [`Error::Timeout`] if
+    /// no response arrives within `timeout`.
+    ///
+    /// Suitable as a bounded health probe: a wedged Firecracker that accepts
+    /// the connection but never answers cannot hang the caller. To bound every
+    /// request instead, connect with
+    /// [`connect_with_timeout()`](crate::connection::connect_with_timeout).
+    pub async fn describe_with_timeout(&self, timeout: Duration) -> Result<InstanceInfo> {
+        tokio::time::timeout(timeout, self.describe())
+            .await
+            .map_err(|_| Error::Timeout("describe_instance response"))?
+    }
+
     /// Get the Firecracker version.
     pub async fn version(&self) -> Result<FirecrackerVersion> {
-        let version = self.client.get_firecracker_version().send().await?;
+        let version = self
+            .retry
+            .run(|| {
+                traced(
+                    "get_firecracker_version",
+                    self.client.get_firecracker_version().send(),
+                )
+            })
+            .await?;
         Ok(version.into_inner())
     }
 
+    /// Get the Firecracker version as `(major, minor, patch)`.
+    ///
+    /// A leading `v` and any pre-release or build suffix (e.g. `-dev`) are
+    /// ignored. Returns [`Error::Other`] if the version cannot be parsed.
+    pub async fn firecracker_semver(&self) -> Result<(u64, u64, u64)> {
+        firecracker_semver(&self.version().await?.firecracker_version)
+    }
+
+    /// Whether the running Firecracker supports `feature`.
+    ///
+    /// Compares [`firecracker_semver()`](Self::firecracker_semver) against
+    /// [`Feature::min_version()`].
+    pub async fn supports(&self, feature: Feature) -> Result<bool> {
+        Ok(feature.supported_by(self.firecracker_semver().await?))
+    }
+
     /// Get the full VM configuration.
     pub async fn config(&self) -> Result<FullVmConfiguration> {
-        let config = self.client.get_export_vm_config().send().await?;
+        let config = self
+            .retry
+            .run(|| {
+                traced(
+                    "get_export_vm_config",
+                    self.client.get_export_vm_config().send(),
+                )
+            })
+            .await?;
         Ok(config.into_inner())
     }
 
+    /// List the configured block devices.
+    ///
+    /// Fetches [`config()`](Self::config); the API has no per-device GET.
+    pub async fn list_drives(&self) -> Result<Vec<Drive>> {
+        Ok(self.config().await?.drives)
+    }
+
+    /// List the configured network interfaces.
+    ///
+    /// Fetches [`config()`](Self::config); the API has no per-device GET.
+    pub async fn list_network_interfaces(&self) -> Result<Vec<NetworkInterface>> {
+        Ok(self.config().await?.network_interfaces)
+    }
+
+    /// List the configured pmem devices.
+    ///
+    /// Fetches [`config()`](Self::config); the API has no per-device GET.
+    pub async fn list_pmem(&self) -> Result<Vec<Pmem>> {
+        Ok(self.config().await?.pmem)
+    }
+
+    /// Compare the running configuration against `desired`.
+    ///
+    /// Fetches [`config()`](Self::config) and reports every field set in
+    /// `desired` whose value differs. Fields left unset (`None`) in `desired`
+    /// are not compared, so Firecracker's filled-in defaults do not show up
+    /// as drift. Drives, network interfaces and pmem devices are matched by
+    /// id; a device present on only one side is reported as a whole.
+    pub async fn diff_config(&self, desired: &FullVmConfiguration) -> Result<Vec<ConfigDiff>> {
+        let current = serde_json::to_value(self.config().await?)?;
+        let desired = serde_json::to_value(desired)?;
+        let mut diffs = Vec::new();
+        diff_values(String::new(), &current, &desired, &mut diffs);
+        Ok(diffs)
+    }
+
+    /// Write the full VM configuration to `path` as JSON.
+    ///
+    /// The file uses Firecracker's `--config-file` field names and can be read
+    /// back with [`VmBuilder::from_json_file()`](crate::VmBuilder::from_json_file).
+    pub async fn export_config_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let config = self.config().await?;
+        let json = serde_json::to_vec_pretty(&config)?;
+        tokio::fs::write(path, json).await?;
+        Ok(())
+    }
+
+    /// Guest boot time in microseconds, as measured by the boot timer device.
+    ///
+    /// Firecracker reports the measurement in its log when the guest signals
+    /// boot completion, so this reads the configured logger's `log_path`.
+    /// Returns `None` if no log file is configured or no measurement has been
+    /// logged, e.g. because `--boot-timer` was not enabled or the log level is
+    /// above `Info`. The log path must be readable from this process (not a
+    /// FIFO, and resolved outside any jailer chroot).
+    pub async fn boot_time_us(&self) -> Result<Option<u64>> {
+        let Some(log_path) = self.config().await?.logger.and_then(|l| l.log_path) else {
+            return Ok(None);
+        };
+        let log = tokio::fs::read_to_string(log_path).await?;
+        Ok(parse_boot_time_us(&log))
+    }
+
+    /// Boot a microVM configured via [`VmBuilder::configure()`](crate::VmBuilder::configure).
+    ///
+    /// Sends the InstanceStart action. Fails if the instance is already running.
+    pub async fn start(&self) -> Result<()> {
+        self.action(InstanceActionInfoActionType::InstanceStart)
+            .await
+    }
+
+    /// Send a synchronous instance action.
+    ///
+    /// The dedicated methods ([`start()`](Self::start),
+    /// [`send_ctrl_alt_del()`](Self::send_ctrl_alt_del),
+    /// [`flush_metrics()`](Self::flush_metrics)) cover the known actions; this
+    /// is the escape hatch for action types the SDK has not wrapped yet.
+    pub async fn action(&self, action: InstanceActionInfoActionType) -> Result<()> {
+        traced(
+            "create_sync_action",
+            self.client
+                .create_sync_action()
+                .body_map(|b| b.action_type(action))
+                .send(),
+        )
+        .await?;
+        Ok(())
+    }
+
     /// Pause the microVM.
     pub async fn pause(&self) -> Result<()> {
-        self.client
-            .patch_vm()
-            .body_map(|b| b.state(VmState::Paused))
-            .send()
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_vm",
+                    self.client
+                        .patch_vm()
+                        .body_map(|b| b.state(VmState::Paused))
+                        .send(),
+                )
+            })
             .await?;
         Ok(())
     }
 
     /// Resume a paused microVM.
     pub async fn resume(&self) -> Result<()> {
-        self.client
-            .patch_vm()
-            .body_map(|b| b.state(VmState::Resumed))
-            .send()
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_vm",
+                    self.client
+                        .patch_vm()
+                        .body_map(|b| b.state(VmState::Resumed))
+                        .send(),
+                )
+            })
             .await?;
         Ok(())
     }
 
+    /// Pause the microVM unless it is already paused.
+    ///
+    /// Checks the state via [`describe()`](Self::describe) first, so repeated
+    /// calls succeed instead of being rejected by Firecracker.
+    pub async fn ensure_paused(&self) -> Result<()> {
+        if self.describe().await?.state == InstanceInfoState::Paused {
+            return Ok(());
+        }
+        self.pause().await
+    }
+
+    /// Resume the microVM unless it is already running.
+    ///
+    /// Checks the state via [`describe()`](Self::describe) first, so repeated
+    /// calls succeed instead of being rejected by Firecracker.
+    pub async fn ensure_resumed(&self) -> Result<()> {
+        if self.describe().await?.state == InstanceInfoState::Running {
+            return Ok(());
+        }
+        self.resume().await
+    }
+
     /// Send Ctrl+Alt+Del to the guest.
     pub async fn send_ctrl_alt_del(&self) -> Result<()> {
-        self.client
-            .create_sync_action()
-            .body_map(|b| b.action_type(InstanceActionInfoActionType::SendCtrlAltDel))
-            .send()
-            .await?;
-        Ok(())
+        self.action(InstanceActionInfoActionType::SendCtrlAltDel)
+            .await
+    }
+
+    /// Ask the guest to shut down and wait until it stops running.
+    ///
+    /// Firecracker has no shutdown action, so this sends Ctrl+Alt+Del (which
+    /// requires guest support, e.g. an i8042 driver on x86_64) and polls
+    /// [`describe()`](Self::describe) until the instance leaves the running
+    /// state or the API goes away because the Firecracker process exited.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if the guest is still running after `timeout`.
+    pub async fn shutdown_and_wait(&self, timeout: Duration) -> Result<()> {
+        self.send_ctrl_alt_del().await?;
+        self.poll_state(timeout, "microVM to shut down", not_running)
+            .await
+    }
+
+    /// Wait until the instance reports `state`.
+    ///
+    /// Polls [`describe()`](Self::describe); an API error ends the wait.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Timeout`] if `state` is not reached within `timeout`.
+    pub async fn wait_for_state(&self, state: InstanceInfoState, timeout: Duration) -> Result<()> {
+        self.poll_state(timeout, "microVM state change", reached(state))
+            .await
+    }
+
+    /// Like [`wait_for_state()`](Self::wait_for_state), returning
+    /// [`Error::Cancelled`] as soon as `token` is cancelled.
+    pub async fn wait_for_state_cancellable(
+        &self,
+        state: InstanceInfoState,
+        timeout: Duration,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        cancellable(token, self.wait_for_state(state, timeout)).await
+    }
+
+    /// Wait until the microVM is running.
+    pub async fn wait_running(&self, timeout: Duration) -> Result<()> {
+        self.poll_state(
+            timeout,
+            "microVM to be running",
+            reached(InstanceInfoState::Running),
+        )
+        .await
+    }
+
+    /// Wait after InstanceStart until the microVM is running.
+    ///
+    /// Like [`wait_running()`](Self::wait_running), but a request that cannot
+    /// connect to the API is reported as [`Error::ProcessExited`]. A reset
+    /// connection is transient and only ends the current poll.
+    pub(crate) async fn wait_for_boot(&self, timeout: Duration) -> Result<()> {
+        self.poll_state(timeout, "microVM to be running", |current| match current {
+            Ok(InstanceInfoState::Running) => Some(Ok(())),
+            Ok(_) => None,
+            Err(e) if e.is_connect_failure() => Some(Err(Error::ProcessExited(None))),
+            Err(e) if e.is_retryable() => None,
+            Err(e) => Some(Err(e)),
+        })
+        .await
+    }
+
+    /// Wait until the microVM is paused.
+    pub async fn wait_paused(&self, timeout: Duration) -> Result<()> {
+        self.poll_state(
+            timeout,
+            "microVM to be paused",
+            reached(InstanceInfoState::Paused),
+        )
+        .await
+    }
+
+    /// Wait until the microVM leaves the running state.
+    ///
+    /// Like [`shutdown_and_wait()`](Self::shutdown_and_wait) without sending
+    /// Ctrl+Alt+Del: an API error counts as stopped, since the Firecracker
+    /// process exits when the guest shuts down.
+    pub async fn wait_not_running(&self, timeout: Duration) -> Result<()> {
+        self.poll_state(timeout, "microVM to stop running", not_running)
+            .await
+    }
+
+    async fn poll_state(
+        &self,
+        timeout: Duration,
+        what: &'static str,
+        done: impl FnMut(Result<InstanceInfoState>) -> Option<Result<()>>,
+    ) -> Result<()> {
+        poll_state(
+            || async { Ok(self.describe().await?.state) },
+            timeout,
+            what,
+            done,
+        )
+        .await
+    }
+
+    /// Like [`shutdown_and_wait()`](Self::shutdown_and_wait), returning
+    /// [`Error::Cancelled`] as soon as `token` is cancelled.
+    pub async fn shutdown_and_wait_cancellable(
+        &self,
+        timeout: Duration,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        cancellable(token, self.shutdown_and_wait(timeout)).await
     }
 
     /// Flush metrics to the configured metrics path.
     pub async fn flush_metrics(&self) -> Result<()> {
-        self.client
-            .create_sync_action()
-            .body_map(|b| b.action_type(InstanceActionInfoActionType::FlushMetrics))
-            .send()
-            .await?;
-        Ok(())
+        self.action(InstanceActionInfoActionType::FlushMetrics)
+            .await
+    }
+
+    /// Flush metrics and return the line Firecracker just wrote to `path`.
+    ///
+    /// `path` must be the regular file given as the metrics path (a named pipe
+    /// cannot be re-read). The flush action only completes once the metrics
+    /// are written, so the last line of the file is the fresh one.
+    pub async fn flush_and_read_metrics(&self, path: &Path) -> Result<serde_json::Value> {
+        self.flush_metrics().await?;
+        let path = path.to_owned();
+        let line = tokio::task::spawn_blocking(move || last_line(&path))
+            .await
+            .map_err(|e| Error::Other(format!("metrics read task failed: {e}")))??;
+        match line {
+            Some(line) => Ok(serde_json::from_str(&line)?),
+            None => Err(Error::Other("metrics file is empty after flush".to_owned())),
+        }
     }
 
     // =========================================================================
@@ -94,54 +456,157 @@ impl Vm {
     ///
     /// The VM should be paused before creating a snapshot.
     pub async fn create_snapshot(&self, snapshot_path: &str, mem_file_path: &str) -> Result<()> {
-        self.client
-            .create_snapshot()
-            .body(SnapshotCreateParams {
-                mem_file_path: mem_file_path.to_owned(),
-                snapshot_path: snapshot_path.to_owned(),
-                snapshot_type: Some(SnapshotCreateParamsSnapshotType::Full),
-            })
-            .send()
-            .await?;
+        traced(
+            "create_snapshot",
+            self.client
+                .create_snapshot()
+                .body(SnapshotCreateParams {
+                    mem_file_path: mem_file_path.to_owned(),
+                    snapshot_path: snapshot_path.to_owned(),
+                    snapshot_type: Some(SnapshotCreateParamsSnapshotType::Full),
+                })
+                .send(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Like [`create_snapshot()`](Self::create_snapshot), then `fsync`s both
+    /// files and their parent directories so the snapshot survives a host crash.
+    ///
+    /// The paths are opened by this process, so they should resolve to the
+    /// same files Firecracker wrote. A path that does not exist here (e.g. a
+    /// chroot-relative path of a jailed process) is skipped: the snapshot has
+    /// been written, but this call cannot make it durable. Other failures to
+    /// sync are returned as [`Error::Io`] after the snapshot was created.
+    pub async fn create_snapshot_durable(
+        &self,
+        snapshot_path: &str,
+        mem_file_path: &str,
+    ) -> Result<()> {
+        self.create_snapshot(snapshot_path, mem_file_path).await?;
+        let paths = [PathBuf::from(snapshot_path), PathBuf::from(mem_file_path)];
+        tokio::task::spawn_blocking(move || sync_files(&paths))
+            .await
+            .map_err(|e| Error::Other(format!("snapshot sync task failed: {e}")))??;
         Ok(())
     }
 
     /// Create a diff snapshot of the microVM.
     ///
     /// The VM should be paused before creating a snapshot.
-    /// Requires `track_dirty_pages` to be enabled in machine configuration.
+    ///
+    /// Before Firecracker 1.13 ([`Feature::DiffSnapshotWithoutTracking`]),
+    /// this requires `track_dirty_pages` in the machine configuration and
+    /// fails with [`Error::Other`] before sending the request if it is off.
+    /// Dirty page tracking is set at boot (or when loading a snapshot), so
+    /// diff snapshots cannot be retrofitted onto a microVM started without it.
     pub async fn create_diff_snapshot(
         &self,
         snapshot_path: &str,
         mem_file_path: &str,
     ) -> Result<()> {
-        self.client
-            .create_snapshot()
-            .body(SnapshotCreateParams {
-                mem_file_path: mem_file_path.to_owned(),
-                snapshot_path: snapshot_path.to_owned(),
-                snapshot_type: Some(SnapshotCreateParamsSnapshotType::Diff),
-            })
-            .send()
-            .await?;
+        if !self.supports(Feature::DiffSnapshotWithoutTracking).await?
+            && !self.machine_configuration().await?.track_dirty_pages
+        {
+            return Err(Error::Other(
+                "diff snapshot requires track_dirty_pages=true at boot".to_owned(),
+            ));
+        }
+        traced(
+            "create_snapshot",
+            self.client
+                .create_snapshot()
+                .body(SnapshotCreateParams {
+                    mem_file_path: mem_file_path.to_owned(),
+                    snapshot_path: snapshot_path.to_owned(),
+                    snapshot_type: Some(SnapshotCreateParamsSnapshotType::Diff),
+                })
+                .send(),
+        )
+        .await?;
         Ok(())
     }
 
+    /// Pause the microVM, take a snapshot, and resume it.
+    ///
+    /// Takes a [diff snapshot](Self::create_diff_snapshot) if `diff` is set,
+    /// otherwise a [full one](Self::create_snapshot). The microVM is resumed
+    /// even if the snapshot fails, so a failed backup never leaves it paused;
+    /// the snapshot error is returned after resuming. A failure to resume
+    /// takes precedence, since the microVM is then still paused. If pausing
+    /// fails, nothing else is attempted.
+    pub async fn snapshot_and_resume(
+        &self,
+        snapshot_path: &str,
+        mem_file_path: &str,
+        diff: bool,
+    ) -> Result<()> {
+        self.pause().await?;
+        let created = if diff {
+            self.create_diff_snapshot(snapshot_path, mem_file_path)
+                .await
+        } else {
+            self.create_snapshot(snapshot_path, mem_file_path).await
+        };
+        self.resume().await?;
+        created
+    }
+
     // =========================================================================
     // Live Updates - Drives
     // =========================================================================
 
     /// Update a drive's properties (hot swap or rate limiting).
+    ///
+    /// This is a post-boot operation: it is accepted while the microVM is
+    /// running or paused, but rejected on a configured microVM that has not
+    /// been started yet. Firecracker's rejection is surfaced as
+    /// [`Error::InvalidStateForOperation`] carrying its `fault_message`.
     pub async fn update_drive(&self, drive_id: &str, update: PartialDrive) -> Result<()> {
-        self.client
-            .patch_guest_drive_by_id()
-            .drive_id(drive_id)
-            .body(update)
-            .send()
-            .await?;
+        self.retry
+            .run(|| {
+                traced_id(
+                    "patch_guest_drive_by_id",
+                    drive_id,
+                    self.client
+                        .patch_guest_drive_by_id()
+                        .drive_id(drive_id)
+                        .body(update.clone())
+                        .send(),
+                )
+            })
+            .await
+            .map_err(|e| e.for_operation("update_drive"))?;
         Ok(())
     }
 
+    /// Eject the media of a drive, e.g. an ISO image, by swapping its
+    /// backing file for `placeholder`.
+    ///
+    /// Firecracker cannot detach a drive from a running microVM: there is
+    /// no hot-unplug, and the guest keeps seeing the device. The closest
+    /// supported operation is to point it at an empty file, which the guest
+    /// sees as a zero-sized disk. `placeholder` must be an existing empty
+    /// file that Firecracker can open (inside the chroot under the jailer),
+    /// and the guest should unmount the device first. Insert new media
+    /// with [`update_drive()`](Self::update_drive).
+    ///
+    /// Only applies to virtio-block drives backed by a host file, not to
+    /// vhost-user drives. The same post-boot restrictions as `update_drive()`
+    /// apply.
+    pub async fn eject_drive(&self, drive_id: &str, placeholder: &str) -> Result<()> {
+        self.update_drive(
+            drive_id,
+            PartialDrive {
+                drive_id: drive_id.to_owned(),
+                path_on_host: Some(placeholder.to_owned()),
+                rate_limiter: None,
+            },
+        )
+        .await
+    }
+
     // =========================================================================
     // Live Updates - Network
     // =========================================================================
@@ -152,49 +617,186 @@ impl Vm {
         iface_id: &str,
         update: PartialNetworkInterface,
     ) -> Result<()> {
-        self.client
-            .patch_guest_network_interface_by_id()
-            .iface_id(iface_id)
-            .body(update)
-            .send()
+        self.retry
+            .run(|| {
+                traced_id(
+                    "patch_guest_network_interface_by_id",
+                    iface_id,
+                    self.client
+                        .patch_guest_network_interface_by_id()
+                        .iface_id(iface_id)
+                        .body(update.clone())
+                        .send(),
+                )
+            })
             .await?;
         Ok(())
     }
 
+    /// Replace only the receive rate limiter of a network interface.
+    pub async fn throttle_rx(&self, iface_id: &str, limiter: RateLimiter) -> Result<()> {
+        self.update_network_interface(
+            iface_id,
+            PartialNetworkInterface {
+                iface_id: iface_id.to_owned(),
+                rx_rate_limiter: Some(limiter),
+                tx_rate_limiter: None,
+            },
+        )
+        .await
+    }
+
+    /// Replace only the transmit rate limiter of a network interface.
+    pub async fn throttle_tx(&self, iface_id: &str, limiter: RateLimiter) -> Result<()> {
+        self.update_network_interface(
+            iface_id,
+            PartialNetworkInterface {
+                iface_id: iface_id.to_owned(),
+                rx_rate_limiter: None,
+                tx_rate_limiter: Some(limiter),
+            },
+        )
+        .await
+    }
+
     // =========================================================================
     // Live Updates - Balloon
     // =========================================================================
 
     /// Get the current balloon device configuration.
     pub async fn balloon_config(&self) -> Result<Balloon> {
-        let balloon = self.client.describe_balloon_config().send().await?;
+        let balloon = self
+            .retry
+            .run(|| {
+                traced(
+                    "describe_balloon_config",
+                    self.client.describe_balloon_config().send(),
+                )
+            })
+            .await?;
         Ok(balloon.into_inner())
     }
 
     /// Get balloon device statistics.
     pub async fn balloon_stats(&self) -> Result<BalloonStats> {
-        let stats = self.client.describe_balloon_stats().send().await?;
+        let stats = self
+            .retry
+            .run(|| {
+                traced(
+                    "describe_balloon_stats",
+                    self.client.describe_balloon_stats().send(),
+                )
+            })
+            .await?;
         Ok(stats.into_inner())
     }
 
     /// Update the balloon device target size.
     pub async fn update_balloon(&self, amount_mib: i64) -> Result<()> {
-        self.client
-            .patch_balloon()
-            .body(BalloonUpdate { amount_mib })
-            .send()
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_balloon",
+                    self.client
+                        .patch_balloon()
+                        .body(BalloonUpdate { amount_mib })
+                        .send(),
+                )
+            })
             .await?;
         Ok(())
     }
 
+    /// Update the balloon target size and wait until the device reaches it.
+    ///
+    /// Inflation and deflation happen asynchronously in the guest, so this polls
+    /// [`balloon_stats()`](Self::balloon_stats) until `actual_mib` is within 1 MiB
+    /// of `amount_mib`, returning the final statistics.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MissingConfig`] if balloon statistics are disabled
+    /// (`stats_polling_interval_s` unset or zero), and [`Error::Timeout`] if the
+    /// target is not reached within `timeout`.
+    pub async fn update_balloon_and_wait(
+        &self,
+        amount_mib: i64,
+        timeout: Duration,
+    ) -> Result<BalloonStats> {
+        self.ensure_balloon_stats().await?;
+        self.update_balloon(amount_mib).await?;
+
+        tokio::time::timeout(timeout, async {
+            loop {
+                let stats = self.balloon_stats().await?;
+                if (stats.actual_mib - amount_mib).abs() <= BALLOON_TOLERANCE_MIB {
+                    return Ok(stats);
+                }
+                tokio::time::sleep(BALLOON_POLL_INTERVAL).await;
+            }
+        })
+        .await
+        .map_err(|_| Error::Timeout("balloon to reach target size"))?
+    }
+
+    /// Poll [`balloon_stats()`](Self::balloon_stats) every `interval`.
+    ///
+    /// The stream ends after yielding its first error. If balloon statistics
+    /// are disabled, the only item is [`Error::MissingConfig`]. Ticks missed
+    /// while the consumer is busy are not replayed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when polled if `interval` is zero.
+    #[cfg(feature = "stream")]
+    pub fn balloon_stats_stream(
+        &self,
+        interval: Duration,
+    ) -> impl futures::Stream<Item = Result<BalloonStats>> + '_ {
+        // `None` ends the stream; `Some(None)` means the first poll is pending.
+        futures::stream::unfold(Some(None), move |state| async move {
+            let mut ticker = match state? {
+                Some(ticker) => ticker,
+                None => {
+                    if let Err(e) = self.ensure_balloon_stats().await {
+                        return Some((Err(e), None));
+                    }
+                    let mut ticker = tokio::time::interval(interval);
+                    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                    ticker
+                }
+            };
+            ticker.tick().await;
+            match self.balloon_stats().await {
+                Ok(stats) => Some((Ok(stats), Some(Some(ticker)))),
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Fail with [`Error::MissingConfig`] unless balloon statistics are enabled.
+    async fn ensure_balloon_stats(&self) -> Result<()> {
+        let config = self.balloon_config().await?;
+        if config.stats_polling_interval_s.unwrap_or(0) == 0 {
+            return Err(Error::MissingConfig("balloon.stats_polling_interval_s"));
+        }
+        Ok(())
+    }
+
     /// Update the balloon statistics polling interval.
     pub async fn update_balloon_stats_interval(&self, stats_polling_interval_s: i64) -> Result<()> {
-        self.client
-            .patch_balloon_stats_interval()
-            .body(BalloonStatsUpdate {
-                stats_polling_interval_s,
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_balloon_stats_interval",
+                    self.client
+                        .patch_balloon_stats_interval()
+                        .body(BalloonStatsUpdate {
+                            stats_polling_interval_s,
+                        })
+                        .send(),
+                )
             })
-            .send()
             .await?;
         Ok(())
     }
@@ -205,25 +807,46 @@ impl Vm {
 
     /// Start a free page hinting run.
     pub async fn start_balloon_hinting(&self, acknowledge_on_stop: Option<bool>) -> Result<()> {
-        self.client
-            .start_balloon_hinting()
-            .body(BalloonStartCmd {
-                acknowledge_on_stop,
+        self.retry
+            .run(|| {
+                traced(
+                    "start_balloon_hinting",
+                    self.client
+                        .start_balloon_hinting()
+                        .body(BalloonStartCmd {
+                            acknowledge_on_stop,
+                        })
+                        .send(),
+                )
             })
-            .send()
             .await?;
         Ok(())
     }
 
     /// Get the balloon hinting status.
     pub async fn balloon_hinting_status(&self) -> Result<BalloonHintingStatus> {
-        let status = self.client.describe_balloon_hinting().send().await?;
+        let status = self
+            .retry
+            .run(|| {
+                traced(
+                    "describe_balloon_hinting",
+                    self.client.describe_balloon_hinting().send(),
+                )
+            })
+            .await?;
         Ok(status.into_inner())
     }
 
     /// Stop a free page hinting run.
     pub async fn stop_balloon_hinting(&self) -> Result<()> {
-        self.client.stop_balloon_hinting().send().await?;
+        self.retry
+            .run(|| {
+                traced(
+                    "stop_balloon_hinting",
+                    self.client.stop_balloon_hinting().send(),
+                )
+            })
+            .await?;
         Ok(())
     }
 
@@ -233,7 +856,15 @@ impl Vm {
 
     /// Get the current machine configuration.
     pub async fn machine_configuration(&self) -> Result<MachineConfiguration> {
-        let config = self.client.get_machine_configuration().send().await?;
+        let config = self
+            .retry
+            .run(|| {
+                traced(
+                    "get_machine_configuration",
+                    self.client.get_machine_configuration().send(),
+                )
+            })
+            .await?;
         Ok(config.into_inner())
     }
 
@@ -241,52 +872,219 @@ impl Vm {
     ///
     /// Pre-boot only. If any parameter has an incorrect value, the whole update fails.
     pub async fn update_machine_config(&self, config: MachineConfiguration) -> Result<()> {
-        self.client
-            .patch_machine_configuration()
-            .body(config)
-            .send()
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_machine_configuration",
+                    self.client
+                        .patch_machine_configuration()
+                        .body(config.clone())
+                        .send(),
+                )
+            })
             .await?;
         Ok(())
     }
 
+    /// Update only the machine configuration fields set in `patch`.
+    ///
+    /// Pre-boot only. The `PATCH /machine-config` body contains just the set
+    /// fields, so Firecracker keeps its current values for the others. If any
+    /// parameter has an incorrect value, the whole update fails.
+    pub async fn patch_machine_config(&self, patch: MachineConfigPatch) -> Result<()> {
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_machine_configuration",
+                    self.send_machine_config_patch(&patch),
+                )
+            })
+            .await
+    }
+
+    /// Send `patch` directly: the generated client only accepts a complete
+    /// [`MachineConfiguration`] as the body.
+    async fn send_machine_config_patch(
+        &self,
+        patch: &MachineConfigPatch,
+    ) -> std::result::Result<(), fc_api::Error<fc_api::types::Error>> {
+        use fc_api::ClientInfo;
+
+        let response = self
+            .client
+            .client()
+            .patch(format!("{}/machine-config", self.client.baseurl()))
+            .header(reqwest::header::ACCEPT, "application/json")
+            .json(patch)
+            .send()
+            .await
+            .map_err(fc_api::Error::CommunicationError)?;
+        match response.status().as_u16() {
+            204 => Ok(()),
+            400 => {
+                let status = response.status();
+                let headers = response.headers().clone();
+                let body = response
+                    .bytes()
+                    .await
+                    .map_err(fc_api::Error::ResponseBodyError)?;
+                let fault = serde_json::from_slice(&body)
+                    .map_err(|e| fc_api::Error::InvalidResponsePayload(body, e))?;
+                Err(fc_api::Error::ErrorResponse(fc_api::ResponseValue::new(
+                    fault, status, headers,
+                )))
+            }
+            _ => Err(fc_api::Error::UnexpectedResponse(response)),
+        }
+    }
+
     /// Get the status of the hotpluggable memory device.
     pub async fn memory_hotplug_status(&self) -> Result<MemoryHotplugStatus> {
-        let status = self.client.get_memory_hotplug().send().await?;
+        let status = self
+            .retry
+            .run(|| {
+                traced(
+                    "get_memory_hotplug",
+                    self.client.get_memory_hotplug().send(),
+                )
+            })
+            .await?;
         Ok(status.into_inner())
     }
 
     /// Update the size of the hotpluggable memory region.
     pub async fn update_memory_hotplug(&self, requested_size_mib: Option<i64>) -> Result<()> {
-        self.client
-            .patch_memory_hotplug()
-            .body(MemoryHotplugSizeUpdate { requested_size_mib })
-            .send()
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_memory_hotplug",
+                    self.client
+                        .patch_memory_hotplug()
+                        .body(MemoryHotplugSizeUpdate { requested_size_mib })
+                        .send(),
+                )
+            })
             .await?;
         Ok(())
     }
 
+    /// Resize guest memory to `target_mib` using whichever device is present.
+    ///
+    /// With a hotpluggable memory device, the hotplugged region is set to
+    /// `target_mib` minus the boot memory size. Otherwise, with a balloon, the
+    /// balloon is inflated or deflated to leave `target_mib` available to the
+    /// guest. The balloon request returns once accepted; use
+    /// [`update_balloon_and_wait()`](Self::update_balloon_and_wait) to wait
+    /// for the guest to follow.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Other`] if neither device is configured, or if
+    /// `target_mib` is outside the range the device can reach.
+    pub async fn resize_memory(&self, target_mib: i64) -> Result<()> {
+        match plan_memory_resize(&self.config().await?, target_mib)? {
+            MemoryResize::Hotplug(requested_size_mib) => {
+                self.update_memory_hotplug(Some(requested_size_mib)).await
+            }
+            MemoryResize::Balloon(amount_mib) => self.update_balloon(amount_mib).await,
+        }
+    }
+
     // =========================================================================
     // MMDS (Microvm Metadata Service)
     // =========================================================================
+    //
+    // The host-side data store methods below behave the same for MMDS V1 and
+    // V2. The version only changes what the guest must do: with V2 it first
+    // requests a session token with `PUT /latest/api/token` (header
+    // `X-metadata-token-ttl-seconds`) and sends it as `X-metadata-token` on
+    // every read. Tokens are only issued to the guest; `mmds_token()` builds
+    // that request for tests that drive the guest side.
+
+    /// Get the configured MMDS version, or `None` if MMDS is not configured.
+    ///
+    /// Use this to decide whether guest-side requests need a V2 session token.
+    pub async fn mmds_version(&self) -> Result<Option<MmdsConfigVersion>> {
+        Ok(self.config().await?.mmds_config.map(|c| c.version))
+    }
+
+    /// Build the `PUT /latest/api/token` request a guest sends to obtain an
+    /// MMDS V2 session token valid for `ttl_seconds`.
+    ///
+    /// The request targets the configured MMDS address and carries
+    /// [`MMDS_TOKEN_TTL_HEADER`]. MMDS only answers traffic from the guest, so
+    /// it has to be replayed from inside the microVM (e.g. as a `curl`
+    /// command); the response body is the token to send as
+    /// [`MMDS_TOKEN_HEADER`] on subsequent reads.
+    ///
+    /// Returns [`Error::MissingConfig`] if MMDS is not configured, and
+    /// [`Error::InvalidConfig`] if `ttl_seconds` is outside 1 to 21600.
+    pub async fn mmds_token(&self, ttl_seconds: u32) -> Result<reqwest::Request> {
+        if !(1..=MMDS_TOKEN_MAX_TTL_SECONDS).contains(&ttl_seconds) {
+            return Err(Error::InvalidConfig(format!(
+                "MMDS token TTL must be between 1 and {MMDS_TOKEN_MAX_TTL_SECONDS} seconds, \
+                 got {ttl_seconds}"
+            )));
+        }
+        let mmds_config = self
+            .config()
+            .await?
+            .mmds_config
+            .ok_or(Error::MissingConfig("mmds_config"))?;
+
+        let url = format!("http://{}/latest/api/token", mmds_config.ipv4_address);
+        let url = reqwest::Url::parse(&url)
+            .map_err(|e| Error::InvalidConfig(format!("invalid MMDS address: {e}")))?;
+        let mut request = reqwest::Request::new(reqwest::Method::PUT, url);
+        request
+            .headers_mut()
+            .insert(MMDS_TOKEN_TTL_HEADER, ttl_seconds.into());
+        Ok(request)
+    }
 
     /// Get the MMDS data store contents.
     pub async fn get_mmds(&self) -> Result<serde_json::Map<String, serde_json::Value>> {
-        let mmds = self.client.get_mmds().send().await?;
+        let mmds = self
+            .retry
+            .run(|| traced("get_mmds", self.client.get_mmds().send()))
+            .await?;
         Ok(mmds.into_inner())
     }
 
     /// Set (replace) the MMDS data store contents.
     pub async fn set_mmds(&self, data: serde_json::Map<String, serde_json::Value>) -> Result<()> {
-        self.client.put_mmds().body(data).send().await?;
+        self.retry
+            .run(|| traced("put_mmds", self.client.put_mmds().body(data.clone()).send()))
+            .await?;
         Ok(())
     }
 
     /// Patch (merge) the MMDS data store contents.
     pub async fn patch_mmds(&self, data: serde_json::Map<String, serde_json::Value>) -> Result<()> {
-        self.client.patch_mmds().body(data).send().await?;
+        self.retry
+            .run(|| {
+                traced(
+                    "patch_mmds",
+                    self.client.patch_mmds().body(data.clone()).send(),
+                )
+            })
+            .await?;
         Ok(())
     }
 
+    /// Set the MMDS value at `pointer`, a JSON Pointer (RFC 6901) such as
+    /// `/latest/creds/token`.
+    ///
+    /// Sends a [`patch_mmds()`](Self::patch_mmds) containing just the nested
+    /// objects leading to the value, creating any that are missing. Every
+    /// reference token is treated as an object key, so array elements cannot
+    /// be addressed. As with any MMDS patch, a `null` value removes the key.
+    ///
+    /// Returns [`Error::InvalidConfig`] if `pointer` is empty or malformed.
+    pub async fn mmds_set_pointer(&self, pointer: &str, value: serde_json::Value) -> Result<()> {
+        self.patch_mmds(pointer_patch(pointer, value)?).await
+    }
+
     // =========================================================================
     // Direct Client Access
     // =========================================================================
@@ -302,6 +1100,393 @@ impl Vm {
     }
 }
 
+/// Partial machine configuration update for [`Vm::patch_machine_config()`].
+///
+/// Only fields that are explicitly set are changed.
+///
+/// # Example
+///
+/// ```no_run
+/// use fc_sdk::vm::MachineConfigPatch;
+///
+/// # async fn example(vm: &fc_sdk::Vm) -> fc_sdk::Result<()> {
+/// vm.patch_machine_config(MachineConfigPatch::new().mem_size_mib(1024))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MachineConfigPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vcpu_count: Option<NonZeroU64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mem_size_mib: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smt: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    track_dirty_pages: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_template: Option<CpuTemplate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    huge_pages: Option<MachineConfigurationHugePages>,
+}
+
+impl MachineConfigPatch {
+    /// Create an empty patch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of vCPUs.
+    pub fn vcpu_count(mut self, vcpu_count: NonZeroU64) -> Self {
+        self.vcpu_count = Some(vcpu_count);
+        self
+    }
+
+    /// Set the guest memory size in MiB.
+    pub fn mem_size_mib(mut self, mem_size_mib: i64) -> Self {
+        self.mem_size_mib = Some(mem_size_mib);
+        self
+    }
+
+    /// Enable or disable simultaneous multithreading.
+    pub fn smt(mut self, smt: bool) -> Self {
+        self.smt = Some(smt);
+        self
+    }
+
+    /// Enable or disable dirty page tracking.
+    pub fn track_dirty_pages(mut self, track_dirty_pages: bool) -> Self {
+        self.track_dirty_pages = Some(track_dirty_pages);
+        self
+    }
+
+    /// Set the CPU template.
+    pub fn cpu_template(mut self, cpu_template: CpuTemplate) -> Self {
+        self.cpu_template = Some(cpu_template);
+        self
+    }
+
+    /// Set the huge pages configuration.
+    pub fn huge_pages(mut self, huge_pages: MachineConfigurationHugePages) -> Self {
+        self.huge_pages = Some(huge_pages);
+        self
+    }
+}
+
+/// A field that differs between the running and the desired configuration.
+///
+/// Returned by [`Vm::diff_config()`]. Displays as
+/// `machine-config.mem_size_mib: 512 -> 1024`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigDiff {
+    /// Dotted path of the field using the API's JSON names; devices are
+    /// indexed by id, e.g. `drives[rootfs].is_read_only`.
+    pub path: String,
+    /// Value reported by Firecracker, or `None` if absent.
+    pub current: Option<serde_json::Value>,
+    /// Desired value, or `None` if the desired configuration lacks it.
+    pub desired: Option<serde_json::Value>,
+}
+
+impl std::fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |value: &Option<serde_json::Value>| match value {
+            Some(value) => value.to_string(),
+            None => "<none>".to_owned(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            show(&self.current),
+            show(&self.desired)
+        )
+    }
+}
+
+/// Id field of the devices in each array of a `FullVmConfiguration`.
+fn device_id_key(path: &str) -> Option<&'static str> {
+    match path {
+        "drives" => Some("drive_id"),
+        "network-interfaces" => Some("iface_id"),
+        "pmem" => Some("id"),
+        _ => None,
+    }
+}
+
+fn diff_values(
+    path: String,
+    current: &serde_json::Value,
+    desired: &serde_json::Value,
+    diffs: &mut Vec<ConfigDiff>,
+) {
+    use serde_json::Value;
+
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+    match (current, desired) {
+        // Unset in the desired configuration: not compared.
+        (_, Value::Null) => {}
+        (Value::Object(current), Value::Object(desired)) => {
+            for (key, desired) in desired {
+                let current = current.get(key).unwrap_or(&Value::Null);
+                diff_values(join(key), current, desired, diffs);
+            }
+        }
+        (Value::Array(current), Value::Array(desired))
+            if let Some(id_key) = device_id_key(&path) =>
+        {
+            let id = |device: &Value| device.get(id_key).cloned().unwrap_or(Value::Null);
+            let label = |device: &Value| match id(device) {
+                Value::String(id) => format!("{path}[{id}]"),
+                other => format!("{path}[{other}]"),
+            };
+            for device in desired {
+                match current.iter().find(|c| id(c) == id(device)) {
+                    Some(found) => diff_values(label(device), found, device, diffs),
+                    None => diffs.push(ConfigDiff {
+                        path: label(device),
+                        current: None,
+                        desired: Some(device.clone()),
+                    }),
+                }
+            }
+            for device in current {
+                if !desired.iter().any(|d| id(d) == id(device)) {
+                    diffs.push(ConfigDiff {
+                        path: label(device),
+                        current: Some(device.clone()),
+                        desired: None,
+                    });
+                }
+            }
+        }
+        (current, desired) if current != desired => diffs.push(ConfigDiff {
+            path,
+            current: (!current.is_null()).then(|| current.clone()),
+            desired: Some(desired.clone()),
+        }),
+        _ => {}
+    }
+}
+
+/// Device request chosen by [`Vm::resize_memory()`].
+#[derive(Debug, PartialEq, Eq)]
+enum MemoryResize {
+    /// New size of the hotplugged region in MiB.
+    Hotplug(i64),
+    /// New balloon size in MiB.
+    Balloon(i64),
+}
+
+fn plan_memory_resize(config: &FullVmConfiguration, target_mib: i64) -> Result<MemoryResize> {
+    let boot_mib = config
+        .machine_config
+        .as_ref()
+        .map_or(0, |machine| machine.mem_size_mib);
+    let out_of_range = |max: i64| {
+        Error::Other(format!(
+            "cannot resize memory to {target_mib} MiB: supported range is {boot_mib}..={max} MiB"
+        ))
+    };
+
+    if let Some(hotplug) = &config.memory_hotplug {
+        let requested = target_mib - boot_mib;
+        let max = hotplug.total_size_mib.unwrap_or(i64::MAX);
+        if requested < 0 || requested > max {
+            return Err(out_of_range(boot_mib.saturating_add(max)));
+        }
+        return Ok(MemoryResize::Hotplug(requested));
+    }
+
+    if config.balloon.is_some() {
+        if !(0..=boot_mib).contains(&target_mib) {
+            return Err(Error::Other(format!(
+                "cannot resize memory to {target_mib} MiB: supported range is 0..={boot_mib} MiB"
+            )));
+        }
+        return Ok(MemoryResize::Balloon(boot_mib - target_mib));
+    }
+
+    Err(Error::Other(
+        "memory cannot be resized post-boot: no memory hotplug or balloon device".to_owned(),
+    ))
+}
+
+/// `fsync` each file and then each distinct parent directory, skipping files
+/// that do not exist for this process.
+fn sync_files(paths: &[PathBuf]) -> std::io::Result<()> {
+    let mut dirs = Vec::new();
+    for path in paths {
+        match std::fs::File::open(path) {
+            Ok(file) => file.sync_all()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    for dir in dirs {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Parse a Firecracker version string as reported by `GET /version`; see
+/// [`Vm::firecracker_semver()`].
+pub(crate) fn firecracker_semver(version: &str) -> Result<(u64, u64, u64)> {
+    let core = version.strip_prefix('v').unwrap_or(version);
+    let core = core.split(['-', '+']).next().unwrap_or_default();
+    parse_version(core)
+        .ok_or_else(|| Error::Other(format!("unparseable Firecracker version: {version}")))
+}
+
+/// Poll `describe` until `done` returns a result, or fail with
+/// [`Error::Timeout`] after `timeout`.
+async fn poll_state<Fut>(
+    mut describe: impl FnMut() -> Fut,
+    timeout: Duration,
+    what: &'static str,
+    mut done: impl FnMut(Result<InstanceInfoState>) -> Option<Result<()>>,
+) -> Result<()>
+where
+    Fut: Future<Output = Result<InstanceInfoState>>,
+{
+    tokio::time::timeout(timeout, async {
+        loop {
+            if let Some(result) = done(describe().await) {
+                return result;
+            }
+            tokio::time::sleep(STATE_POLL_INTERVAL).await;
+        }
+    })
+    .await
+    .map_err(|_| Error::Timeout(what))?
+}
+
+/// Completion check for [`poll_state()`]: `state` reached, or an API error.
+fn reached(
+    state: InstanceInfoState,
+) -> impl FnMut(Result<InstanceInfoState>) -> Option<Result<()>> {
+    move |current| match current {
+        Ok(current) if current == state => Some(Ok(())),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+    }
+}
+
+/// Completion check for [`poll_state()`]: anything but running, including the
+/// API going away.
+fn not_running(current: Result<InstanceInfoState>) -> Option<Result<()>> {
+    match current {
+        Ok(InstanceInfoState::Running) => None,
+        _ => Some(Ok(())),
+    }
+}
+
+/// Read the last non-empty line of `path`, scanning backwards from the end so
+/// long-running metrics files are not read in full.
+fn last_line(path: &Path) -> std::io::Result<Option<String>> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    const CHUNK: u64 = 16 * 1024;
+
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut tail = Vec::new();
+    let mut start = len;
+    loop {
+        let trimmed = tail.trim_ascii_end();
+        if let Some(newline) = trimmed.iter().rposition(|&b| b == b'\n') {
+            return Ok(Some(
+                String::from_utf8_lossy(&trimmed[newline + 1..]).into_owned(),
+            ));
+        }
+        if start == 0 {
+            return Ok((!trimmed.is_empty()).then(|| String::from_utf8_lossy(trimmed).into_owned()));
+        }
+        let read_from = start.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (start - read_from) as usize];
+        file.seek(SeekFrom::Start(read_from))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&tail);
+        tail = chunk;
+        start = read_from;
+    }
+}
+
+/// Run `operation` until it completes or `token` is cancelled.
+pub(crate) async fn cancellable<T>(
+    token: &CancellationToken,
+    operation: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        biased;
+        () = token.cancelled() => Err(Error::Cancelled),
+        result = operation => result,
+    }
+}
+
+/// Build the minimal nested object that sets `pointer` to `value`.
+fn pointer_patch(
+    pointer: &str,
+    value: serde_json::Value,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let invalid = || Error::InvalidConfig(format!("invalid MMDS JSON pointer: {pointer:?}"));
+    let tokens = pointer
+        .strip_prefix('/')
+        .ok_or_else(invalid)?
+        .split('/')
+        .map(|token| {
+            // Decode in one pass so `~01` becomes `~1`, not `/` (RFC 6901 section 4).
+            let mut rest = token;
+            let mut key = String::new();
+            while let Some(i) = rest.find('~') {
+                key.push_str(&rest[..i]);
+                match rest.as_bytes().get(i + 1) {
+                    Some(b'0') => key.push('~'),
+                    Some(b'1') => key.push('/'),
+                    _ => return Err(invalid()),
+                }
+                rest = &rest[i + 2..];
+            }
+            key.push_str(rest);
+            Ok(key)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut tokens = tokens.into_iter().rev();
+    let mut patch = serde_json::Map::new();
+    patch.insert(tokens.next().ok_or_else(invalid)?, value);
+    for key in tokens {
+        let mut parent = serde_json::Map::new();
+        parent.insert(key, serde_json::Value::Object(patch));
+        patch = parent;
+    }
+    Ok(patch)
+}
+
+/// Extract the most recent boot timer measurement from a Firecracker log.
+///
+/// Matches lines like `Guest-boot-time =  12345 us 12 ms, ...`.
+fn parse_boot_time_us(log: &str) -> Option<u64> {
+    log.lines().rev().find_map(|line| {
+        let (_, rest) = line.split_once("Guest-boot-time =")?;
+        rest.split_whitespace().next()?.parse().ok()
+    })
+}
+
 // =============================================================================
 // Standalone Functions
 // =============================================================================
@@ -309,7 +1494,9 @@ impl Vm {
 /// Restore a microVM from a snapshot.
 ///
 /// This must be called on a fresh Firecracker process (before configuring any
-/// resources other than logger and metrics).
+/// resources other than logger and metrics). The snapshot and memory files are
+/// checked locally before the load request is sent; see [`RestoreBuilder`] for
+/// finer control.
 ///
 /// # Arguments
 ///
@@ -338,13 +1525,553 @@ impl Vm {
 /// # }
 /// ```
 pub async fn restore(socket_path: impl AsRef<Path>, params: SnapshotLoadParams) -> Result<Vm> {
-    let client = connect(socket_path);
-    client.load_snapshot().body(params).send().await?;
-    Ok(Vm::new(client))
+    RestoreBuilder::new(socket_path, params).load().await
 }
 
 /// Restore a microVM from a snapshot using an existing client.
+///
+/// Like [`restore()`], this checks that the snapshot files exist locally first;
+/// use [`RestoreBuilder::skip_local_checks()`] to opt out.
 pub async fn restore_with_client(client: Client, params: SnapshotLoadParams) -> Result<Vm> {
-    client.load_snapshot().body(params).send().await?;
-    Ok(Vm::new(client))
+    RestoreBuilder::with_client(client, params).load().await
+}
+
+/// Spawn a fresh Firecracker process and restore a microVM from a snapshot
+/// on it.
+///
+/// This is the safe way to restore: the snapshot is always loaded onto the
+/// process that was just spawned for it, and the returned [`RunningVm`] keeps
+/// the two together. If the load fails the process is killed. Use
+/// [`FirecrackerProcess::restore_vm()`] to customize the [`RestoreBuilder`].
+///
+/// # Example
+///
+/// ```no_run
+/// use fc_sdk::{FirecrackerProcessBuilder, restore_new_process, types::*};
+///
+/// # async fn example(params: SnapshotLoadParams) -> fc_sdk::Result<()> {
+/// let process = FirecrackerProcessBuilder::new_auto("/usr/bin/firecracker");
+/// let vm = restore_new_process(process, params).await?;
+/// vm.resume().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`FirecrackerProcess::restore_vm()`]: crate::FirecrackerProcess::restore_vm
+pub async fn restore_new_process(
+    process: FirecrackerProcessBuilder,
+    params: SnapshotLoadParams,
+) -> Result<RunningVm> {
+    process
+        .spawn()
+        .await?
+        .restore_vm(params, |restore| restore)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[tokio::test]
+    async fn test_describe_with_timeout() {
+        let socket_path = crate::process::unique_socket_path();
+        // Accepts connections but never answers, like a wedged process.
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        let server = tokio::spawn(async move {
+            let mut streams = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                streams.push(stream);
+            }
+        });
+
+        let vm = Vm::from_client(crate::connection::connect(&socket_path));
+        let result = vm.describe_with_timeout(Duration::from_millis(50)).await;
+        assert!(matches!(
+            result,
+            Err(Error::Timeout("describe_instance response"))
+        ));
+        server.abort();
+        std::fs::remove_file(&socket_path).ok();
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_list_devices() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let mock = MockClient::new().unwrap();
+        mock.respond(
+            "GET",
+            "/vm/config",
+            200,
+            Some(json!({
+                "drives": [{
+                    "drive_id": "rootfs",
+                    "is_root_device": true,
+                    "path_on_host": "/rootfs.ext4",
+                }],
+                "network-interfaces": [{ "iface_id": "eth0", "host_dev_name": "tap0" }],
+            })),
+        );
+        let vm = mock.vm();
+
+        let drives = vm.list_drives().await.unwrap();
+        assert_eq!(drives.len(), 1);
+        assert_eq!(drives[0].drive_id, "rootfs");
+        let ifaces = vm.list_network_interfaces().await.unwrap();
+        assert_eq!(ifaces[0].host_dev_name, "tap0");
+        assert!(vm.list_pmem().await.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_patch_machine_config_sends_only_set_fields() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let mock = MockClient::new().unwrap();
+        let vm = mock.vm();
+        vm.patch_machine_config(MachineConfigPatch::new().mem_size_mib(1024))
+            .await
+            .unwrap();
+        assert_eq!(mock.calls(), ["PATCH /machine-config"]);
+        assert_eq!(
+            mock.requests()[0].body,
+            Some(json!({ "mem_size_mib": 1024 }))
+        );
+
+        mock.respond_fault(
+            "PATCH",
+            "/machine-config",
+            400,
+            "The update operation is not allowed after boot.",
+        );
+        let err = vm
+            .patch_machine_config(MachineConfigPatch::new().smt(true))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.fault_message(),
+            Some("The update operation is not allowed after boot.")
+        );
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_eject_drive() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let mock = MockClient::new().unwrap();
+        mock.vm().eject_drive("cdrom", "/empty.img").await.unwrap();
+        let request = &mock.requests()[0];
+        assert_eq!(request.method, "PATCH");
+        assert_eq!(request.path, "/drives/cdrom");
+        assert_eq!(
+            request.body,
+            Some(json!({ "drive_id": "cdrom", "path_on_host": "/empty.img" }))
+        );
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_diff_snapshot_requires_dirty_tracking_before_1_13() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let mock = MockClient::new().unwrap();
+        mock.vm()
+            .create_diff_snapshot("/snap", "/mem")
+            .await
+            .unwrap();
+        assert_eq!(mock.calls(), ["GET /version", "PUT /snapshot/create"]);
+
+        let mock = MockClient::new().unwrap();
+        mock.respond(
+            "GET",
+            "/version",
+            200,
+            Some(json!({ "firecracker_version": "1.12.1" })),
+        );
+        mock.respond(
+            "GET",
+            "/machine-config",
+            200,
+            Some(json!({ "vcpu_count": 1, "mem_size_mib": 128 })),
+        );
+        let err = mock
+            .vm()
+            .create_diff_snapshot("/snap", "/mem")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("track_dirty_pages=true"));
+        assert_eq!(mock.calls(), ["GET /version", "GET /machine-config"]);
+    }
+
+    #[test]
+    fn test_pointer_patch() {
+        use serde_json::json;
+
+        let patch = pointer_patch("/latest/creds/token", json!("secret")).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(patch),
+            json!({ "latest": { "creds": { "token": "secret" } } })
+        );
+        let patch = pointer_patch("/a~1b/c~0d/~01", json!(1)).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(patch),
+            json!({ "a/b": { "c~d": { "~1": 1 } } })
+        );
+        let patch = pointer_patch("/", json!(null)).unwrap();
+        assert_eq!(serde_json::Value::Object(patch), json!({ "": null }));
+
+        for pointer in ["", "latest", "/bad~2escape", "/trailing~"] {
+            assert!(matches!(
+                pointer_patch(pointer, json!(1)),
+                Err(Error::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_boot_reports_unreachable_api_as_exit() {
+        let vm = Vm::from_client(crate::connection::connect(
+            crate::process::unique_socket_path(),
+        ));
+        let result = vm.wait_for_boot(Duration::from_secs(1)).await;
+        assert!(matches!(result, Err(Error::ProcessExited(None))));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_boot_survives_connection_reset() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let socket_path = crate::process::unique_socket_path();
+        let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+        tokio::spawn(async move {
+            // Closing with the request still unread resets the connection.
+            let (first, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(first);
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut line = String::new();
+            while stream.read_line(&mut line).await.unwrap() > 2 {
+                line.clear();
+            }
+            let body =
+                r#"{"app_name":"Firecracker","id":"vm","state":"Running","vmm_version":"1.14.0"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            stream
+                .get_mut()
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+        });
+
+        let vm = Vm::from_client(crate::connection::connect(&socket_path));
+        vm.wait_for_boot(Duration::from_secs(5)).await.unwrap();
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_snapshot_and_resume_resumes_after_failure() {
+        use crate::connection::MockClient;
+
+        let mock = MockClient::new().unwrap();
+        mock.respond_fault("PUT", "/snapshot/create", 400, "No space left on device");
+        let err = mock
+            .vm()
+            .snapshot_and_resume("/snap", "/mem", false)
+            .await
+            .unwrap_err();
+        assert_eq!(err.fault_message(), Some("No space left on device"));
+        assert_eq!(
+            mock.calls(),
+            ["PATCH /vm", "PUT /snapshot/create", "PATCH /vm"]
+        );
+        let resume = &mock.requests()[2];
+        assert_eq!(resume.body, Some(serde_json::json!({ "state": "Resumed" })));
+    }
+
+    #[test]
+    fn test_parse_boot_time_us() {
+        let log = "\
+2024-01-01T00:00:00.000000000 [fc:main] Running Firecracker v1.12.1
+2024-01-01T00:00:00.100000000 [fc:fc_vcpu 0] Guest-boot-time =  12345 us 12 ms,  11000 CPU us 11 CPU ms
+2024-01-01T00:00:05.000000000 [fc:fc_vcpu 0] Guest-boot-time =   9876 us 9 ms,   9000 CPU us 9 CPU ms
+";
+        assert_eq!(parse_boot_time_us(log), Some(9876));
+        assert_eq!(
+            parse_boot_time_us("[fc:main] Running Firecracker v1.12.1\n"),
+            None
+        );
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_mmds_version_and_token() {
+        use crate::connection::MockClient;
+        use serde_json::json;
+
+        let mock = MockClient::new().unwrap();
+        let vm = mock.vm();
+        assert_eq!(vm.mmds_version().await.unwrap(), None);
+        assert!(matches!(
+            vm.mmds_token(60).await,
+            Err(Error::MissingConfig("mmds_config"))
+        ));
+
+        mock.respond(
+            "GET",
+            "/vm/config",
+            200,
+            Some(json!({
+                "mmds-config": {
+                    "version": "V2",
+                    "network_interfaces": ["eth0"],
+                    "ipv4_address": "169.254.170.2",
+                },
+            })),
+        );
+        assert_eq!(
+            vm.mmds_version().await.unwrap(),
+            Some(MmdsConfigVersion::V2)
+        );
+
+        let request = vm.mmds_token(60).await.unwrap();
+        assert_eq!(request.method(), reqwest::Method::PUT);
+        assert_eq!(
+            request.url().as_str(),
+            "http://169.254.170.2/latest/api/token"
+        );
+        assert_eq!(request.headers()[MMDS_TOKEN_TTL_HEADER], "60");
+        assert!(matches!(
+            vm.mmds_token(0).await,
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            vm.mmds_token(MMDS_TOKEN_MAX_TTL_SECONDS + 1).await,
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_cancellable_stops_pending_operation() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let result = cancellable(&token, std::future::pending::<Result<()>>()).await;
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn test_wait_for_state_cancellable() {
+        use crate::connection::MockClient;
+
+        // The mock instance stays running, so waiting for a pause never ends.
+        let mock = MockClient::new().unwrap();
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+
+        let result = mock
+            .vm()
+            .wait_for_state_cancellable(InstanceInfoState::Paused, Duration::from_secs(30), &token)
+            .await;
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn test_sync_files() {
+        let dir = TempDir::new("sync");
+        let snapshot = dir.join("vm.snap");
+        let mem = dir.join("vm.mem");
+        std::fs::write(&snapshot, b"snap").unwrap();
+        std::fs::write(&mem, b"mem").unwrap();
+
+        assert!(sync_files(&[snapshot.clone(), mem]).is_ok());
+        // Not visible to this process, e.g. inside a jailer chroot.
+        assert!(sync_files(&[dir.join("missing")]).is_ok());
+        assert!(sync_files(&[snapshot.join("not-a-dir")]).is_err());
+    }
+
+    #[test]
+    fn test_plan_memory_resize() {
+        use fc_api::types::MemoryHotplugConfig;
+
+        let mut config = FullVmConfiguration {
+            machine_config: Some(MachineConfiguration {
+                vcpu_count: NonZeroU64::new(1).unwrap(),
+                mem_size_mib: 512,
+                smt: false,
+                track_dirty_pages: false,
+                cpu_template: None,
+                huge_pages: None,
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            plan_memory_resize(&config, 256),
+            Err(Error::Other(_))
+        ));
+
+        config.balloon = Some(Balloon {
+            amount_mib: 0,
+            deflate_on_oom: true,
+            free_page_hinting: None,
+            free_page_reporting: None,
+            stats_polling_interval_s: None,
+        });
+        assert_eq!(
+            plan_memory_resize(&config, 384).unwrap(),
+            MemoryResize::Balloon(128)
+        );
+        assert!(plan_memory_resize(&config, 1024).is_err());
+
+        // Hotplug takes precedence over the balloon.
+        config.memory_hotplug = Some(MemoryHotplugConfig {
+            block_size_mib: 2,
+            slot_size_mib: 128,
+            total_size_mib: Some(1024),
+        });
+        assert_eq!(
+            plan_memory_resize(&config, 1024).unwrap(),
+            MemoryResize::Hotplug(512)
+        );
+        assert!(plan_memory_resize(&config, 2048).is_err());
+        assert!(plan_memory_resize(&config, 256).is_err());
+    }
+
+    async fn run_poll(
+        states: Vec<Result<InstanceInfoState>>,
+        done: impl FnMut(Result<InstanceInfoState>) -> Option<Result<()>>,
+    ) -> (Result<()>, usize) {
+        let mut states = std::collections::VecDeque::from(states);
+        let mut calls = 0;
+        let result = poll_state(
+            || {
+                calls += 1;
+                let next = states
+                    .pop_front()
+                    .unwrap_or(Ok(InstanceInfoState::NotStarted));
+                async move { next }
+            },
+            Duration::from_secs(2),
+            "test state",
+            done,
+        )
+        .await;
+        (result, calls)
+    }
+
+    #[tokio::test]
+    async fn test_poll_state_waits_for_target() {
+        let (result, calls) = run_poll(
+            vec![
+                Ok(InstanceInfoState::NotStarted),
+                Ok(InstanceInfoState::NotStarted),
+                Ok(InstanceInfoState::Running),
+            ],
+            reached(InstanceInfoState::Running),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+
+        let (result, calls) = run_poll(
+            vec![
+                Ok(InstanceInfoState::Running),
+                Err(Error::Other("connection refused".into())),
+            ],
+            not_running,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_poll_state_stops_on_error_and_times_out() {
+        let (result, _) = run_poll(
+            vec![Err(Error::Other("gone".into()))],
+            reached(InstanceInfoState::Paused),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Other(_))));
+
+        let result = poll_state(
+            || async { Ok(InstanceInfoState::Running) },
+            Duration::from_millis(250),
+            "test state",
+            reached(InstanceInfoState::Paused),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Timeout("test state"))));
+    }
+
+    #[test]
+    fn test_last_line() {
+        let dir = TempDir::new("last-line");
+        let path = dir.join("metrics.json");
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(last_line(&path).unwrap(), None);
+
+        std::fs::write(&path, "{\"only\":1}").unwrap();
+        assert_eq!(last_line(&path).unwrap().as_deref(), Some("{\"only\":1}"));
+
+        // Longer than one chunk, with a trailing newline.
+        let long = "x".repeat(40 * 1024);
+        std::fs::write(&path, format!("{{\"old\":1}}\n{long}\n")).unwrap();
+        assert_eq!(last_line(&path).unwrap(), Some(long));
+    }
+
+    #[test]
+    fn test_diff_values() {
+        use serde_json::json;
+
+        let current = json!({
+            "machine-config": { "vcpu_count": 2, "mem_size_mib": 512, "smt": false },
+            "drives": [
+                { "drive_id": "rootfs", "is_read_only": false, "cache_type": "Unsafe" },
+                { "drive_id": "scratch", "is_read_only": false },
+            ],
+        });
+        let desired = json!({
+            "machine-config": { "vcpu_count": 2, "mem_size_mib": 1024, "smt": null },
+            "drives": [
+                { "drive_id": "rootfs", "is_read_only": true },
+                { "drive_id": "data", "is_read_only": false },
+            ],
+            "balloon": null,
+        });
+
+        let mut diffs = Vec::new();
+        diff_values(String::new(), &current, &desired, &mut diffs);
+        // Key order depends on serde_json's `preserve_order` feature.
+        let mut shown: Vec<_> = diffs.iter().map(ToString::to_string).collect();
+        shown.sort();
+        assert_eq!(
+            shown,
+            [
+                r#"drives[data]: <none> -> {"drive_id":"data","is_read_only":false}"#,
+                "drives[rootfs].is_read_only: false -> true",
+                r#"drives[scratch]: {"drive_id":"scratch","is_read_only":false} -> <none>"#,
+                "machine-config.mem_size_mib: 512 -> 1024",
+            ]
+        );
+    }
 }
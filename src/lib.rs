@@ -18,14 +18,7 @@
 //!         boot_args: Some("console=ttyS0 reboot=k panic=1".into()),
 //!         initrd_path: None,
 //!     })
-//!     .machine_config(MachineConfiguration {
-//!         vcpu_count: std::num::NonZeroU64::new(2).unwrap(),
-//!         mem_size_mib: 512,
-//!         smt: false,
-//!         track_dirty_pages: false,
-//!         cpu_template: None,
-//!         huge_pages: None,
-//!     })
+//!     .machine_config_simple(std::num::NonZeroU64::new(2).unwrap(), 512)
 //!     .start()
 //!     .await?;
 //!
@@ -1,8 +1,10 @@
 use std::env;
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use fc_sdk::{FirecrackerProcessBuilder, JailerProcessBuilder};
 use sha2::{Digest, Sha256};
@@ -36,11 +38,18 @@ pub enum BundledRuntimeError {
         actual: String,
     },
 
+    /// Bundled binary failed detached signature verification, or a signature
+    /// was configured without a verifier to check it.
+    SignatureInvalid { binary: &'static str, path: PathBuf },
+
     /// Unsupported platform for Firecracker release-based bundled mode.
     UnsupportedPlatform { os: String, arch: String },
 
     /// Invalid Firecracker release version.
     InvalidReleaseVersion(String),
+
+    /// An option required by the operation was not set.
+    MissingConfig(&'static str),
 }
 
 impl std::error::Error for BundledRuntimeError {
@@ -85,6 +94,13 @@ impl fmt::Display for BundledRuntimeError {
                     path.display()
                 )
             }
+            Self::SignatureInvalid { binary, path } => {
+                write!(
+                    f,
+                    "signature verification failed for {binary} ({})",
+                    path.display()
+                )
+            }
             Self::UnsupportedPlatform { os, arch } => {
                 write!(
                     f,
@@ -97,6 +113,7 @@ impl fmt::Display for BundledRuntimeError {
                     "invalid Firecracker release version: {version}; expected vX.Y.Z"
                 )
             }
+            Self::MissingConfig(option) => write!(f, "missing required configuration: {option}"),
         }
     }
 }
@@ -107,6 +124,27 @@ impl From<std::io::Error> for BundledRuntimeError {
     }
 }
 
+/// Convert into an [`std::io::Error`], e.g. [`BundledRuntimeError::BinaryNotFound`]
+/// becomes [`ErrorKind::NotFound`](std::io::ErrorKind::NotFound).
+impl From<BundledRuntimeError> for std::io::Error {
+    fn from(err: BundledRuntimeError) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match &err {
+            BundledRuntimeError::Io(e) => e.kind(),
+            BundledRuntimeError::BinaryNotFound { .. } => ErrorKind::NotFound,
+            BundledRuntimeError::BinaryNotExecutable(_) => ErrorKind::PermissionDenied,
+            BundledRuntimeError::InvalidSha256 { .. }
+            | BundledRuntimeError::InvalidReleaseVersion(_)
+            | BundledRuntimeError::MissingConfig(_) => ErrorKind::InvalidInput,
+            BundledRuntimeError::ChecksumMismatch { .. }
+            | BundledRuntimeError::SignatureInvalid { .. } => ErrorKind::InvalidData,
+            BundledRuntimeError::UnsupportedPlatform { .. } => ErrorKind::Unsupported,
+        };
+        Self::new(kind, err)
+    }
+}
+
 /// Result type for bundled runtime resolution.
 pub type Result<T> = std::result::Result<T, BundledRuntimeError>;
 
@@ -122,6 +160,123 @@ pub enum BundledMode {
     BundledThenSystem,
     /// Try system binaries first, then fall back to bundled binaries.
     SystemThenBundled,
+    /// Try only the candidates from the configured [`BinaryResolver`]; see
+    /// [`BundledRuntimeOptions::binary_resolver()`].
+    Custom,
+}
+
+/// Where a resolved binary was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinarySource {
+    /// Explicit path from an `FC_SDK_FIRECRACKER_BIN`/`FC_SDK_JAILER_BIN` override.
+    EnvOverride,
+    /// Upstream release naming (`{binary}-vX.Y.Z-{arch}`) under a bundle root.
+    ReleaseLayout,
+    /// Generic layout under a bundle root (e.g. `{root}/{os}-{arch}/{binary}`).
+    BundleDir,
+    /// A directory on the system `PATH`.
+    System,
+    /// A candidate from a [`BinaryResolver`].
+    Custom,
+}
+
+/// A resolved binary together with how it was selected.
+///
+/// Returned by [`BundledRuntimeOptions::resolve_firecracker()`] and
+/// [`BundledRuntimeOptions::resolve_jailer()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedBinary {
+    /// Path to the binary.
+    pub path: PathBuf,
+    /// Which candidate layout matched.
+    pub source: BinarySource,
+    /// Release version in the file name; set for [`BinarySource::ReleaseLayout`].
+    pub release_version: Option<String>,
+    /// Release architecture in the file name; set for [`BinarySource::ReleaseLayout`].
+    pub arch: Option<String>,
+    /// Whether the binary matched a configured SHA256.
+    pub checksum_verified: bool,
+    /// Whether the binary passed detached signature verification.
+    pub signature_verified: bool,
+}
+
+/// Verifies a detached signature over a resolved binary.
+///
+/// Implement this to plug a signature scheme into
+/// [`BundledRuntimeOptions::signature_verifier()`]. With the `minisign`
+/// feature, [`BundledRuntimeOptions::public_key()`] installs a
+/// [`MinisignVerifier`].
+pub trait SignatureVerifier: fmt::Debug + Send + Sync {
+    /// Return whether `signature` is a valid signature of `binary`.
+    fn verify(&self, binary: &Path, signature: &Path) -> std::io::Result<bool>;
+}
+
+/// Produces candidate paths for [`BundledMode::Custom`].
+///
+/// Candidates are tried in order and go through the same executable,
+/// checksum and signature checks as the built-in layouts. Implemented for
+/// closures taking the binary file name (e.g. `firecracker`).
+pub trait BinaryResolver: Send + Sync {
+    /// Return candidate paths for the binary named `binary`, most preferred
+    /// first.
+    fn candidates(&self, binary: &str) -> Vec<PathBuf>;
+}
+
+impl<F> BinaryResolver for F
+where
+    F: Fn(&str) -> Vec<PathBuf> + Send + Sync,
+{
+    fn candidates(&self, binary: &str) -> Vec<PathBuf> {
+        self(binary)
+    }
+}
+
+impl fmt::Debug for dyn BinaryResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BinaryResolver")
+    }
+}
+
+/// [`SignatureVerifier`] for minisign detached signatures.
+#[cfg(feature = "minisign")]
+#[derive(Debug, Clone)]
+pub struct MinisignVerifier {
+    public_key: Vec<u8>,
+}
+
+#[cfg(feature = "minisign")]
+impl MinisignVerifier {
+    /// Create a verifier from a minisign public key, either the contents of
+    /// a `.pub` file or the bare base64 key.
+    pub fn new(public_key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            public_key: public_key.into(),
+        }
+    }
+}
+
+#[cfg(feature = "minisign")]
+impl SignatureVerifier for MinisignVerifier {
+    fn verify(&self, binary: &Path, signature: &Path) -> std::io::Result<bool> {
+        use minisign_verify::{PublicKey, Signature};
+
+        let Ok(public_key) = std::str::from_utf8(&self.public_key) else {
+            return Ok(false);
+        };
+        let public_key = public_key.trim();
+        let Ok(public_key) =
+            PublicKey::decode(public_key).or_else(|_| PublicKey::from_base64(public_key))
+        else {
+            return Ok(false);
+        };
+        let Ok(signature) = Signature::decode(&std::fs::read_to_string(signature)?) else {
+            return Ok(false);
+        };
+
+        // Accept both prehashed and legacy signatures.
+        let binary = std::fs::read(binary)?;
+        Ok(public_key.verify(&binary, &signature, true).is_ok())
+    }
 }
 
 /// Options for resolving Firecracker/Jailer binaries in bundled mode.
@@ -132,12 +287,18 @@ pub enum BundledMode {
 pub struct BundledRuntimeOptions {
     mode: BundledMode,
     bundle_root: Option<PathBuf>,
+    cache_dir: Option<PathBuf>,
     release_version: Option<String>,
     firecracker_bin_name: String,
     jailer_bin_name: String,
     ensure_executable: bool,
     firecracker_sha256: Option<String>,
     jailer_sha256: Option<String>,
+    firecracker_signature: Option<PathBuf>,
+    jailer_signature: Option<PathBuf>,
+    signature_verifier: Option<Arc<dyn SignatureVerifier>>,
+    binary_resolver: Option<Arc<dyn BinaryResolver>>,
+    ignore_env: bool,
 }
 
 impl Default for BundledRuntimeOptions {
@@ -145,12 +306,18 @@ impl Default for BundledRuntimeOptions {
         Self {
             mode: BundledMode::BundledThenSystem,
             bundle_root: None,
+            cache_dir: None,
             release_version: None,
             firecracker_bin_name: "firecracker".to_owned(),
             jailer_bin_name: "jailer".to_owned(),
             ensure_executable: true,
             firecracker_sha256: None,
             jailer_sha256: None,
+            firecracker_signature: None,
+            jailer_signature: None,
+            signature_verifier: None,
+            binary_resolver: None,
+            ignore_env: false,
         }
     }
 }
@@ -185,6 +352,16 @@ impl BundledRuntimeOptions {
         self
     }
 
+    /// Set a cache directory for release artifacts installed with
+    /// [`install_release()`](Self::install_release).
+    ///
+    /// It is searched like a [`bundle_root()`](Self::bundle_root), after the
+    /// bundle root itself.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
     /// Set Firecracker release version (e.g., `v1.10.0`).
     ///
     /// When set, bundled lookup prioritizes upstream release naming.
@@ -223,23 +400,99 @@ impl BundledRuntimeOptions {
         self
     }
 
+    /// Optional detached signature file for firecracker binary.
+    ///
+    /// Requires a verifier via [`public_key()`](Self::public_key) or
+    /// [`signature_verifier()`](Self::signature_verifier).
+    pub fn firecracker_signature(mut self, path: impl Into<PathBuf>) -> Self {
+        self.firecracker_signature = Some(path.into());
+        self
+    }
+
+    /// Optional detached signature file for jailer binary.
+    ///
+    /// Requires a verifier via [`public_key()`](Self::public_key) or
+    /// [`signature_verifier()`](Self::signature_verifier).
+    pub fn jailer_signature(mut self, path: impl Into<PathBuf>) -> Self {
+        self.jailer_signature = Some(path.into());
+        self
+    }
+
+    /// Verify signatures with minisign using `public_key`.
+    #[cfg(feature = "minisign")]
+    pub fn public_key(self, public_key: impl Into<Vec<u8>>) -> Self {
+        self.signature_verifier(MinisignVerifier::new(public_key))
+    }
+
+    /// Verify signatures with a custom [`SignatureVerifier`].
+    pub fn signature_verifier(mut self, verifier: impl SignatureVerifier + 'static) -> Self {
+        self.signature_verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    /// Resolve binaries with a custom [`BinaryResolver`], switching to
+    /// [`BundledMode::Custom`].
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use firecracker::runtime::bundled::BundledRuntimeOptions;
+    ///
+    /// let options = BundledRuntimeOptions::new().binary_resolver(|binary: &str| {
+    ///     vec![
+    ///         PathBuf::from("/etc/my-app/bin").join(binary),
+    ///         PathBuf::from("/opt/firecracker").join(binary),
+    ///         PathBuf::from("/usr/bin").join(binary),
+    ///     ]
+    /// });
+    /// ```
+    pub fn binary_resolver(mut self, resolver: impl BinaryResolver + 'static) -> Self {
+        self.binary_resolver = Some(Arc::new(resolver));
+        self.mode = BundledMode::Custom;
+        self
+    }
+
+    /// Ignore the `FC_SDK_*` environment variable overrides.
+    ///
+    /// Resolution then depends only on explicitly configured values (plus
+    /// `PATH` in the system modes), which keeps it reproducible across hosts.
+    pub fn ignore_env(mut self, ignore: bool) -> Self {
+        self.ignore_env = ignore;
+        self
+    }
+
     /// Resolve path to firecracker binary.
     pub fn resolve_firecracker_bin(&self) -> Result<PathBuf> {
+        self.resolve_firecracker().map(|resolved| resolved.path)
+    }
+
+    /// Resolve path to jailer binary.
+    pub fn resolve_jailer_bin(&self) -> Result<PathBuf> {
+        self.resolve_jailer().map(|resolved| resolved.path)
+    }
+
+    /// Resolve the firecracker binary, reporting which candidate was chosen.
+    pub fn resolve_firecracker(&self) -> Result<ResolvedBinary> {
         self.resolve_binary(
             "firecracker",
             &self.firecracker_bin_name,
             "FC_SDK_FIRECRACKER_BIN",
-            self.firecracker_sha256.as_deref(),
+            Expected {
+                sha256: self.firecracker_sha256.as_deref(),
+                signature: self.firecracker_signature.as_deref(),
+            },
         )
     }
 
-    /// Resolve path to jailer binary.
-    pub fn resolve_jailer_bin(&self) -> Result<PathBuf> {
+    /// Resolve the jailer binary, reporting which candidate was chosen.
+    pub fn resolve_jailer(&self) -> Result<ResolvedBinary> {
         self.resolve_binary(
             "jailer",
             &self.jailer_bin_name,
             "FC_SDK_JAILER_BIN",
-            self.jailer_sha256.as_deref(),
+            Expected {
+                sha256: self.jailer_sha256.as_deref(),
+                signature: self.jailer_signature.as_deref(),
+            },
         )
     }
 
@@ -270,13 +523,93 @@ impl BundledRuntimeOptions {
         ))
     }
 
+    /// Install an unpacked upstream release into the
+    /// [`cache_dir()`](Self::cache_dir), atomically.
+    ///
+    /// `extracted` is the `release-vX.Y.Z-{arch}` directory from the release
+    /// archive for the configured [`release_version()`](Self::release_version).
+    /// Its contents are copied into a hidden staging directory in the cache,
+    /// the binaries are checked against any configured SHA256, and the
+    /// staging directory is then renamed to `{cache_dir}/release-vX.Y.Z-{arch}`.
+    ///
+    /// Resolution only probes final paths, so an install interrupted midway
+    /// is never picked up (its staging directory is left behind). Processes
+    /// sharing a cache may install concurrently: the first rename wins and
+    /// the others return its directory. Returns the installed directory.
+    ///
+    /// Downloading and unpacking the archive is left to the caller.
+    pub fn install_release(&self, extracted: &Path) -> Result<PathBuf> {
+        let cache_dir = self
+            .cache_dir
+            .as_ref()
+            .ok_or(BundledRuntimeError::MissingConfig("cache_dir"))?;
+        let version = self
+            .resolve_release_version()?
+            .ok_or(BundledRuntimeError::MissingConfig("release_version"))?;
+        let arch = current_release_arch()?;
+        let release_dir = format!("release-{version}-{arch}");
+        let dest = cache_dir.join(&release_dir);
+        if dest.is_dir() {
+            return Ok(dest);
+        }
+
+        fs::create_dir_all(cache_dir)?;
+        let staging = cache_dir.join(format!(
+            ".{release_dir}.tmp-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        let staged = self
+            .stage_release(extracted, &staging, &version, &arch)
+            .and_then(|()| Ok(fs::rename(&staging, &dest)?));
+        match staged {
+            Ok(()) => Ok(dest),
+            Err(e) => {
+                fs::remove_dir_all(&staging).ok();
+                // Lost the race against another installer.
+                if matches!(e, BundledRuntimeError::Io(_)) && dest.is_dir() {
+                    Ok(dest)
+                } else {
+                    Err(e)
+                }
+            }
+        }
+    }
+
+    fn stage_release(
+        &self,
+        extracted: &Path,
+        staging: &Path,
+        version: &str,
+        arch: &str,
+    ) -> Result<()> {
+        copy_dir(extracted, staging)?;
+        for (label, name, sha256) in [
+            (
+                "firecracker",
+                &self.firecracker_bin_name,
+                &self.firecracker_sha256,
+            ),
+            ("jailer", &self.jailer_bin_name, &self.jailer_sha256),
+        ] {
+            if let Some(sha256) = sha256 {
+                let path = staging.join(format!("{name}-{version}-{arch}"));
+                verify_sha256(label, &path, sha256)?;
+            }
+        }
+        Ok(())
+    }
+
     fn resolve_binary(
         &self,
         binary_label: &'static str,
         default_name: &str,
         env_override: &str,
-        expected_sha256: Option<&str>,
-    ) -> Result<PathBuf> {
+        expected: Expected<'_>,
+    ) -> Result<ResolvedBinary> {
         let mut searched = Vec::new();
         let bundled_enabled = matches!(
             self.mode,
@@ -296,12 +629,15 @@ impl BundledRuntimeOptions {
             None
         };
 
-        if let Some(override_value) = env::var_os(env_override) {
+        if let Some(override_value) = self.env_var(env_override) {
             let override_path = PathBuf::from(override_value);
             let mut override_candidates = Vec::new();
 
             if looks_like_path(&override_path) {
-                override_candidates.push(override_path);
+                override_candidates.push(Candidate {
+                    path: override_path,
+                    source: BinarySource::EnvOverride,
+                });
             } else if let Some(name) = override_path.to_str() {
                 override_candidates.extend(system_candidates(name));
                 if bundled_enabled {
@@ -314,13 +650,10 @@ impl BundledRuntimeOptions {
                 }
             }
 
-            if let Some(path) = self.first_valid(
-                binary_label,
-                override_candidates,
-                expected_sha256,
-                &mut searched,
-            )? {
-                return Ok(path);
+            if let Some(candidate) =
+                self.first_valid(binary_label, override_candidates, expected, &mut searched)?
+            {
+                return Ok(candidate.resolved(release_version, release_arch, expected));
             }
         }
 
@@ -356,15 +689,22 @@ impl BundledRuntimeOptions {
                     release_arch.as_deref(),
                 ));
             }
+            BundledMode::Custom => {
+                if let Some(resolver) = &self.binary_resolver {
+                    mode_candidates.extend(resolver.candidates(default_name).into_iter().map(
+                        |path| Candidate {
+                            path,
+                            source: BinarySource::Custom,
+                        },
+                    ));
+                }
+            }
         }
 
-        if let Some(path) = self.first_valid(
-            binary_label,
-            mode_candidates,
-            expected_sha256,
-            &mut searched,
-        )? {
-            return Ok(path);
+        if let Some(candidate) =
+            self.first_valid(binary_label, mode_candidates, expected, &mut searched)?
+        {
+            return Ok(candidate.resolved(release_version, release_arch, expected));
         }
 
         Err(BundledRuntimeError::BinaryNotFound {
@@ -376,25 +716,32 @@ impl BundledRuntimeOptions {
     fn first_valid(
         &self,
         binary_label: &'static str,
-        candidates: Vec<PathBuf>,
-        expected_sha256: Option<&str>,
+        candidates: Vec<Candidate>,
+        expected: Expected<'_>,
         searched: &mut Vec<PathBuf>,
-    ) -> Result<Option<PathBuf>> {
-        for candidate in dedupe_paths(candidates) {
-            searched.push(candidate.clone());
-            if !candidate.is_file() {
+    ) -> Result<Option<Candidate>> {
+        for candidate in candidates {
+            if searched.contains(&candidate.path) {
+                continue;
+            }
+            searched.push(candidate.path.clone());
+            let path = &candidate.path;
+            if !path.is_file() {
                 continue;
             }
 
             if self.ensure_executable {
-                ensure_executable(&candidate)?;
+                ensure_executable(path)?;
             }
-            if !is_executable(&candidate)? {
-                return Err(BundledRuntimeError::BinaryNotExecutable(candidate));
+            if !is_executable(path)? {
+                return Err(BundledRuntimeError::BinaryNotExecutable(candidate.path));
             }
 
-            if let Some(expected) = expected_sha256 {
-                verify_sha256(binary_label, &candidate, expected)?;
+            if let Some(sha256) = expected.sha256 {
+                verify_sha256(binary_label, path, sha256)?;
+            }
+            if let Some(signature) = expected.signature {
+                self.verify_signature(binary_label, path, signature)?;
             }
 
             return Ok(Some(candidate));
@@ -402,11 +749,32 @@ impl BundledRuntimeOptions {
         Ok(None)
     }
 
+    fn verify_signature(
+        &self,
+        binary_label: &'static str,
+        path: &Path,
+        signature: &Path,
+    ) -> Result<()> {
+        let verified = match &self.signature_verifier {
+            Some(verifier) => verifier.verify(path, signature)?,
+            None => false,
+        };
+        if verified {
+            Ok(())
+        } else {
+            Err(BundledRuntimeError::SignatureInvalid {
+                binary: binary_label,
+                path: path.to_path_buf(),
+            })
+        }
+    }
+
     fn resolve_release_version(&self) -> Result<Option<String>> {
         let resolved = if let Some(version) = &self.release_version {
             Some(version.clone())
         } else {
-            env::var("FC_SDK_FIRECRACKER_RELEASE").ok()
+            self.env_var("FC_SDK_FIRECRACKER_RELEASE")
+                .and_then(|v| v.into_string().ok())
         };
 
         if let Some(version) = &resolved
@@ -418,6 +786,15 @@ impl BundledRuntimeOptions {
         Ok(resolved)
     }
 
+    /// Read an `FC_SDK_*` override unless [`ignore_env`](Self::ignore_env) is set.
+    fn env_var(&self, key: &str) -> Option<std::ffi::OsString> {
+        if self.ignore_env {
+            None
+        } else {
+            env::var_os(key)
+        }
+    }
+
     fn bundle_roots(&self) -> Vec<PathBuf> {
         let mut roots = Vec::new();
 
@@ -425,7 +802,11 @@ impl BundledRuntimeOptions {
             roots.push(root.clone());
         }
 
-        if let Some(root) = env::var_os("FC_SDK_BUNDLED_DIR") {
+        if let Some(dir) = &self.cache_dir {
+            roots.push(dir.clone());
+        }
+
+        if let Some(root) = self.env_var("FC_SDK_BUNDLED_DIR") {
             roots.push(PathBuf::from(root));
         }
 
@@ -447,16 +828,20 @@ fn looks_like_path(path: &Path) -> bool {
         || path.to_string_lossy().contains(std::path::MAIN_SEPARATOR)
 }
 
-fn system_candidates(binary_name: &str) -> Vec<PathBuf> {
+fn system_candidates(binary_name: &str) -> Vec<Candidate> {
+    let system = |path| Candidate {
+        path,
+        source: BinarySource::System,
+    };
     let name_path = PathBuf::from(binary_name);
     if looks_like_path(&name_path) {
-        return vec![name_path];
+        return vec![system(name_path)];
     }
 
     let mut paths = Vec::new();
     if let Some(path_var) = env::var_os("PATH") {
         for dir in env::split_paths(&path_var) {
-            paths.push(dir.join(binary_name));
+            paths.push(system(dir.join(binary_name)));
         }
     }
     paths
@@ -467,25 +852,35 @@ fn bundled_candidates(
     roots: &[PathBuf],
     release_version: Option<&str>,
     release_arch: Option<&str>,
-) -> Vec<PathBuf> {
+) -> Vec<Candidate> {
     let mut candidates = Vec::new();
     let keys = target_keys();
+    let release = |path| Candidate {
+        path,
+        source: BinarySource::ReleaseLayout,
+    };
+    let bundle = |path| Candidate {
+        path,
+        source: BinarySource::BundleDir,
+    };
 
     for root in roots {
         if let (Some(version), Some(arch)) = (release_version, release_arch) {
             let versioned_bin = format!("{binary_name}-{version}-{arch}");
             let release_dir = format!("release-{version}-{arch}");
 
-            candidates.push(root.join(&release_dir).join(&versioned_bin));
-            candidates.push(root.join(&release_dir).join("bin").join(&versioned_bin));
-            candidates.push(root.join(&versioned_bin));
+            candidates.push(release(root.join(&release_dir).join(&versioned_bin)));
+            candidates.push(release(
+                root.join(&release_dir).join("bin").join(&versioned_bin),
+            ));
+            candidates.push(release(root.join(&versioned_bin)));
         }
 
         for key in &keys {
-            candidates.push(root.join(key).join(binary_name));
-            candidates.push(root.join(key).join("bin").join(binary_name));
+            candidates.push(bundle(root.join(key).join(binary_name)));
+            candidates.push(bundle(root.join(key).join("bin").join(binary_name)));
         }
-        candidates.push(root.join(binary_name));
+        candidates.push(bundle(root.join(binary_name)));
     }
 
     candidates
@@ -515,6 +910,38 @@ fn current_release_arch() -> Result<String> {
     }
 }
 
+/// A path to probe and the layout it belongs to.
+struct Candidate {
+    path: PathBuf,
+    source: BinarySource,
+}
+
+impl Candidate {
+    fn resolved(
+        self,
+        release_version: Option<String>,
+        release_arch: Option<String>,
+        expected: Expected<'_>,
+    ) -> ResolvedBinary {
+        let release = self.source == BinarySource::ReleaseLayout;
+        ResolvedBinary {
+            path: self.path,
+            source: self.source,
+            release_version: release_version.filter(|_| release),
+            arch: release_arch.filter(|_| release),
+            checksum_verified: expected.sha256.is_some(),
+            signature_verified: expected.signature.is_some(),
+        }
+    }
+}
+
+/// Integrity expectations for a resolved binary.
+#[derive(Clone, Copy)]
+struct Expected<'a> {
+    sha256: Option<&'a str>,
+    signature: Option<&'a Path>,
+}
+
 fn dedupe_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
     let mut unique = Vec::new();
     for path in paths {
@@ -565,21 +992,25 @@ fn normalize_sha256(raw: &str) -> Option<String> {
 }
 
 fn is_valid_release_version(value: &str) -> bool {
-    if !value.starts_with('v') {
-        return false;
-    }
-    let mut parts = value[1..].split('.');
-    match (parts.next(), parts.next(), parts.next(), parts.next()) {
-        (Some(major), Some(minor), Some(patch), None) => {
-            !major.is_empty()
-                && !minor.is_empty()
-                && !patch.is_empty()
-                && major.chars().all(|c| c.is_ascii_digit())
-                && minor.chars().all(|c| c.is_ascii_digit())
-                && patch.chars().all(|c| c.is_ascii_digit())
+    value
+        .strip_prefix('v')
+        .and_then(fc_sdk::version::parse_version)
+        .is_some()
+}
+
+/// Recursively copy the directory `src` to `dst`, which must not exist.
+fn copy_dir(src: &Path, dst: &Path) -> std::io::Result<()> {
+    fs::create_dir(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
         }
-        _ => false,
     }
+    Ok(())
 }
 
 fn is_executable(path: &Path) -> std::io::Result<bool> {
@@ -641,6 +1072,53 @@ mod tests {
         assert_eq!(resolved, binary_path);
     }
 
+    #[test]
+    fn test_install_release_into_cache() {
+        let Ok(arch) = current_release_arch() else {
+            return;
+        };
+        let temp = temp_dir("install-release");
+        let extracted = temp.join("extracted");
+        write_executable(&extracted.join(format!("firecracker-v1.12.1-{arch}")));
+        let cache = temp.join("cache");
+        let opts = BundledRuntimeOptions::new()
+            .mode(BundledMode::BundledOnly)
+            .cache_dir(&cache)
+            .release_version("v1.12.1")
+            .ignore_env(true);
+
+        // A mismatching checksum leaves nothing behind.
+        let err = opts
+            .clone()
+            .firecracker_sha256("0".repeat(64))
+            .install_release(&extracted)
+            .unwrap_err();
+        assert!(matches!(err, BundledRuntimeError::ChecksumMismatch { .. }));
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 0);
+
+        let installed = opts.install_release(&extracted).unwrap();
+        assert_eq!(installed, cache.join(format!("release-v1.12.1-{arch}")));
+        assert_eq!(opts.install_release(&extracted).unwrap(), installed);
+        let resolved = opts.resolve_firecracker().unwrap();
+        assert_eq!(resolved.source, BinarySource::ReleaseLayout);
+        assert!(resolved.path.starts_with(&installed));
+
+        assert!(matches!(
+            BundledRuntimeOptions::new().install_release(&extracted),
+            Err(BundledRuntimeError::MissingConfig("cache_dir"))
+        ));
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let err = std::io::Error::from(BundledRuntimeError::BinaryNotFound {
+            binary: "firecracker",
+            searched: vec![PathBuf::from("/opt/fc/firecracker")],
+        });
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("/opt/fc/firecracker"));
+    }
+
     #[test]
     fn test_release_layout_resolution() {
         let temp = temp_dir("release-layout");
@@ -660,6 +1138,50 @@ mod tests {
         assert_eq!(resolved, binary_path);
     }
 
+    #[test]
+    fn test_resolve_reports_release_layout() {
+        let temp = temp_dir("resolve-metadata");
+        let version = "v1.12.0";
+        let arch = env::consts::ARCH;
+        let binary_path = temp
+            .join(format!("release-{version}-{arch}"))
+            .join(format!("firecracker-{version}-{arch}"));
+        write_executable(&binary_path);
+
+        let resolved = BundledRuntimeOptions::new()
+            .mode(BundledMode::BundledOnly)
+            .bundle_root(&temp)
+            .release_version(version)
+            .ignore_env(true)
+            .resolve_firecracker()
+            .unwrap();
+        assert_eq!(
+            resolved,
+            ResolvedBinary {
+                path: binary_path,
+                source: BinarySource::ReleaseLayout,
+                release_version: Some(version.to_owned()),
+                arch: Some(arch.to_owned()),
+                checksum_verified: false,
+                signature_verified: false,
+            }
+        );
+
+        let generic = temp
+            .join(format!("{}-{}", env::consts::OS, arch))
+            .join("jailer");
+        write_executable(&generic);
+        let resolved = BundledRuntimeOptions::new()
+            .mode(BundledMode::BundledOnly)
+            .bundle_root(&temp)
+            .release_version(version)
+            .ignore_env(true)
+            .resolve_jailer()
+            .unwrap();
+        assert_eq!(resolved.source, BinarySource::BundleDir);
+        assert_eq!(resolved.release_version, None);
+    }
+
     #[test]
     fn test_builder_wrappers() {
         let temp = temp_dir("builder-wrapper");
@@ -704,6 +1226,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_custom_resolver() {
+        let temp = temp_dir("custom-resolver");
+        let binary_path = temp.join("opt").join("firecracker");
+        write_executable(&binary_path);
+
+        let root = temp.clone();
+        let opts = BundledRuntimeOptions::new()
+            .ignore_env(true)
+            .binary_resolver(move |binary: &str| {
+                vec![
+                    root.join("config").join(binary),
+                    root.join("opt").join(binary),
+                ]
+            });
+        let resolved = opts.resolve_firecracker().unwrap();
+        assert_eq!(resolved.path, binary_path);
+        assert_eq!(resolved.source, BinarySource::Custom);
+
+        // Custom candidates are still checksummed.
+        let err = opts
+            .firecracker_sha256("0000000000000000000000000000000000000000000000000000000000000000")
+            .resolve_firecracker_bin()
+            .unwrap_err();
+        assert!(matches!(err, BundledRuntimeError::ChecksumMismatch { .. }));
+    }
+
     #[test]
     fn test_invalid_release_version_rejected() {
         let temp = temp_dir("invalid-release-version");
@@ -744,6 +1293,92 @@ mod tests {
         }
     }
 
+    #[derive(Debug)]
+    struct ContentsVerifier;
+
+    impl SignatureVerifier for ContentsVerifier {
+        fn verify(&self, binary: &Path, signature: &Path) -> std::io::Result<bool> {
+            Ok(fs::read(binary)? == fs::read(signature)?)
+        }
+    }
+
+    #[test]
+    fn test_signature_verification() {
+        let temp = temp_dir("signature");
+        let binary_path = temp
+            .join(format!("{}-{}", env::consts::OS, env::consts::ARCH))
+            .join("firecracker");
+        write_executable(&binary_path);
+        let good = temp.join("good.sig");
+        let bad = temp.join("bad.sig");
+        fs::write(&good, b"test-binary").unwrap();
+        fs::write(&bad, b"tampered").unwrap();
+
+        let opts = BundledRuntimeOptions::new()
+            .mode(BundledMode::BundledOnly)
+            .bundle_root(&temp)
+            .signature_verifier(ContentsVerifier);
+        let resolved = opts
+            .clone()
+            .firecracker_signature(&good)
+            .resolve_firecracker_bin()
+            .unwrap();
+        assert_eq!(resolved, binary_path);
+
+        let err = opts
+            .firecracker_signature(&bad)
+            .resolve_firecracker_bin()
+            .unwrap_err();
+        match err {
+            BundledRuntimeError::SignatureInvalid { binary, path } => {
+                assert_eq!(binary, "firecracker");
+                assert_eq!(path, binary_path);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "minisign")]
+    #[test]
+    fn test_minisign_verifier() {
+        let temp = temp_dir("minisign");
+        let binary = temp.join("firecracker");
+        let signature = temp.join("firecracker.minisig");
+        fs::write(&binary, b"test").unwrap();
+        fs::write(
+            &signature,
+            "untrusted comment: signature from minisign secret key\n\
+             RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\n\
+             trusted comment: timestamp:1555779966\tfile:test\n\
+             QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==\n",
+        )
+        .unwrap();
+
+        let verifier =
+            MinisignVerifier::new("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3");
+        assert!(verifier.verify(&binary, &signature).unwrap());
+
+        fs::write(&binary, b"Test").unwrap();
+        assert!(!verifier.verify(&binary, &signature).unwrap());
+    }
+
+    #[test]
+    fn test_signature_without_verifier_is_rejected() {
+        let temp = temp_dir("signature-no-verifier");
+        let binary_path = temp
+            .join(format!("{}-{}", env::consts::OS, env::consts::ARCH))
+            .join("firecracker");
+        write_executable(&binary_path);
+
+        let err = BundledRuntimeOptions::new()
+            .mode(BundledMode::BundledOnly)
+            .bundle_root(&temp)
+            .firecracker_signature(temp.join("firecracker.minisig"))
+            .resolve_firecracker_bin()
+            .unwrap_err();
+        assert!(matches!(err, BundledRuntimeError::SignatureInvalid { .. }));
+    }
+
     fn temp_dir(prefix: &str) -> PathBuf {
         let dir = env::temp_dir().join(format!(
             "firecracker-runtime-{prefix}-{}-{}",
@@ -776,4 +1411,16 @@ mod tests {
             fs::set_permissions(path, perm).unwrap();
         }
     }
+
+    #[test]
+    fn test_ignore_env() {
+        // PATH is always set, so it stands in for the FC_SDK_* overrides.
+        assert!(BundledRuntimeOptions::new().env_var("PATH").is_some());
+        assert!(
+            BundledRuntimeOptions::new()
+                .ignore_env(true)
+                .env_var("PATH")
+                .is_none()
+        );
+    }
 }